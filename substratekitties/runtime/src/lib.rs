@@ -97,7 +97,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("substratekitties"),
 	impl_name: create_runtime_str!("substratekitties"),
 	authoring_version: 3,
-	spec_version: 3,
+	spec_version: 5,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
 };
@@ -191,6 +191,13 @@ impl sudo::Trait for Runtime {
 
 impl substratekitties::Trait for Runtime {
 	type Event = Event;
+	type GeneAlgorithm = substratekitties::XorMixGenes;
+	type OnKittyTransfer = ();
+	type Currency = Balances;
+	type TransferFees = Balances;
+	type AccountValidator = ();
+	type TransferGate = ();
+	type CooldownSchedule = substratekitties::DoublingCooldown;
 }
 
 /// Used for the module template in `./template.rs`
@@ -213,7 +220,7 @@ construct_runtime!(
 		Sudo: sudo,
 		// Used for the module template in `./template.rs`
 		TemplateModule: template::{Module, Call, Storage, Event<T>},
-		Substratekitties: substratekitties::{Module, Call, Storage, Event<T>},
+		Substratekitties: substratekitties::{Module, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
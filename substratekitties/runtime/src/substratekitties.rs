@@ -1,20 +1,563 @@
 use support::{decl_storage, decl_module, StorageValue, StorageMap,
-    dispatch::Result, ensure, decl_event, traits::Currency};
-use system::ensure_signed;
-use runtime_primitives::traits::{As, Hash, Zero};
+    dispatch::Result, ensure, decl_event, traits::{Currency, ReservableCurrency}};
+use system::{ensure_signed, ensure_root};
+use runtime_primitives::traits::{As, CheckedAdd, CheckedMul, CheckedSub, Hash, One, Saturating, Zero};
 use parity_codec::{Encode, Decode};
+use rstd::prelude::*;
 
+/// The maximum length in bytes of a kitty's name.
+pub const MAX_NAME_LENGTH: usize = 32;
+
+/// The longest DNA prefix `find_by_dna_prefix` searches for.
+pub const MAX_DNA_PREFIX_LENGTH: usize = 16;
+
+/// Trait bytes at or above this are rare. Without `RARE_TRAIT_PERCENT` one kitty in
+/// sixteen gets each rare trait.
+pub const RARE_TRAIT_FLOOR: u8 = 0xf0;
+
+/// How many blocks a generation 0 parent rests after breeding under `DoublingCooldown`.
+pub const COOLDOWN_BLOCKS: u64 = 10;
+
+/// A whole kitty in `KittyShares`, in basis points.
+pub const TOTAL_SHARES: u32 = 10_000;
+
+/// The layout of this module's storage, kept in `StorageVersion`. Version 0 stored kitties
+/// as `KittyV0`, and 1 added `name`, `birth_block` and `for_sale`.
+pub const STORAGE_VERSION: u32 = 1;
+
+/// A kitty as kept in `Kitties`.
+///
+/// Adding a field changes the SCALE encoding of `Kitty`, so kitties stored by an
+/// older runtime will no longer decode. Any such upgrade must either start from a
+/// fresh chain or ship a migration that re-encodes every entry of `Kitties`
+/// (iterating `AllKittiesArray`) before the new runtime reads them.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Kitty<Hash, Balance, BlockNumber> {
+    id: Hash,
+    dna: Hash,
+    price: Balance,
+    gen: u64,
+    name: Vec<u8>,
+    birth_block: BlockNumber,
+    for_sale: bool,
+}
+
+/// A kitty as kept in `Kitties` at storage version 0, before any field was added. Only
+/// `migrate_kitties` reads these.
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub struct Kitty<Hash, Balance> {
+pub struct KittyV0<Hash, Balance> {
     id: Hash,
     dna: Hash,
     price: Balance,
     gen: u64,
 }
 
-pub trait Trait: balances::Trait {
+/// Everything a client usually wants to know about a kitty, read in one go by `kitty_info`.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct KittyInfo<AccountId, Hash, Balance, BlockNumber> {
+    pub kitty: Kitty<Hash, Balance, BlockNumber>,
+    pub owner: AccountId,
+    pub approved: Option<AccountId>,
+    pub for_sale: bool,
+    pub listing_expiry: Option<BlockNumber>,
+}
+
+/// How a kitty is on offer right now, as worked out by `sale_status`.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum SaleStatus<Balance, BlockNumber> {
+    NotForSale,
+    /// Listed with `set_price` at this price.
+    FixedPrice(Balance),
+    /// In an English or a Dutch auction that ends at this block.
+    Auction(BlockNumber),
+    /// Offered as a sire for this fee.
+    Sire(Balance),
+}
+
+/// An English auction of a kitty. The kitty stays with its `seller` until the auction is settled.
+///
+/// The `reserve` is left out of every event, but like all storage it can still be read
+/// by anyone who looks, so it only keeps honest clients from showing it.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Auction<AccountId, Balance, BlockNumber> {
+    seller: AccountId,
+    start_price: Balance,
+    high_bidder: Option<AccountId>,
+    high_bid: Balance,
+    reserve: Balance,
+    end_block: BlockNumber,
+}
+
+/// A Dutch auction of a kitty. The price falls in a straight line from `start_price` to
+/// `end_price` over `duration` blocks and then stays at `end_price`.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct DutchAuction<AccountId, Balance, BlockNumber> {
+    seller: AccountId,
+    start_price: Balance,
+    end_price: Balance,
+    start_block: BlockNumber,
+    duration: BlockNumber,
+}
+
+/// The payments that make up one sale, as worked out by `sale_payments`.
+struct SalePayments<AccountId, Balance> {
+    /// Every transfer from the buyer, in the order they are made.
+    payments: Vec<(AccountId, Balance)>,
+    /// What the transfers cost the buyer in all.
+    total: Balance,
+    fee: Option<(AccountId, Balance)>,
+    royalty: Option<(AccountId, Balance)>,
+}
+
+/// Why a call into this module failed.
+///
+/// Calls still return the `&'static str` of `dispatch::Result`, so clients see
+/// `as_str()` of these. New variants only ever go at the end, which keeps the
+/// encoded index of every existing one stable.
+#[derive(Encode, Decode, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Error {
+    KittyNotExists,
+    KittyExists,
+    NoOwner,
+    NotOwner,
+    FromNotOwner,
+    NotApproved,
+    ApproveSelf,
+    TransferToSelf,
+    Overflow,
+    NonceOverflow,
+    MaxSupply,
+    OwnKittyLimit,
+    RecipientKittyLimit,
+    BatchTooLarge,
+    DuplicateInBatch,
+    NameTooLong,
+    NotForSale,
+    ListedForSale,
+    ListingExpired,
+    TooExpensive,
+    BuyOwnKitty,
+    InsufficientBalance,
+    FeesExceedPrice,
+    BreedWithSelf,
+    Resting,
+    NotOwnerOfParent,
+    NotOwnerOfBothParents,
+    NotASire,
+    SireFeeTooHigh,
+    InAuction,
+    NotInAuction,
+    ZeroDuration,
+    StartBelowEndPrice,
+    BidOnOwnKitty,
+    BidBelowStart,
+    BidTooLow,
+    AuctionNotOver,
+    AuctionOver,
+    OfferOnOwnKitty,
+    ZeroOffer,
+    NoOffer,
+    NoOfferToWithdraw,
+    Locked,
+    NotLocked,
+    Paused,
+    Infertile,
+    AlreadyClaimed,
+    PriceBelowMinimum,
+    PriceAboveMaximum,
+    SwapWithSelf,
+    NoSwap,
+    NotCounterparty,
+    SwapStale,
+    Gen0Limit,
+    DnaExists,
+    NameRecentlyChanged,
+    InconsistentStorage,
+    GenerationTooLow,
+    InvalidRecipient,
+    BreedingPaused,
+    NoGift,
+    NotGiftRecipient,
+    NotGiftGiver,
+    GiftHeld,
+    TransferredTooRecently,
+    NotMinter,
+    RecipientNotPermitted,
+    NoTreasury,
+    TreasuryTooLow,
+    TooTired,
+    Leased,
+    LeaseToSelf,
+    ZeroLeaseDuration,
+    NoLeaseOffer,
+    MaxGenerationReached,
+    ZeroShares,
+    NotEnoughShares,
+    OwnerNeedsMajority,
+    TooManyShareholders,
+    CoOwned,
+    CannotAffordFees,
+    BelowExistentialDeposit,
+    TooManySwaps,
+    TooManyOffers,
+}
+
+impl Error {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Error::KittyNotExists => "This cat does not exist",
+            Error::KittyExists => "Kitty already exists",
+            Error::NoOwner => "No owner for this kitty",
+            Error::NotOwner => "You do not own this kitty",
+            Error::FromNotOwner => "`from` does not own this kitty",
+            Error::NotApproved => "You are not approved to transfer this kitty",
+            Error::ApproveSelf => "Cannot approve yourself",
+            Error::TransferToSelf => "Cannot transfer to yourself",
+            Error::Overflow => "Arithmetic overflow",
+            Error::NonceOverflow => "Nonce overflow",
+            Error::MaxSupply => "Maximum kitty supply reached",
+            Error::OwnKittyLimit => "Your kitty limit reached",
+            Error::RecipientKittyLimit => "Recipient kitty limit reached",
+            Error::BatchTooLarge => "Too many kitties in one batch",
+            Error::DuplicateInBatch => "Duplicate kitty in batch",
+            Error::NameTooLong => "Kitty name is too long",
+            Error::NotForSale => "This kitty is not for sale",
+            Error::ListedForSale => "Kitty is listed for sale",
+            Error::ListingExpired => "Listing expired",
+            Error::TooExpensive => "kitty is too expensive",
+            Error::BuyOwnKitty => "You can't buy your own kitty",
+            Error::InsufficientBalance => "Not enough free balance to buy this kitty",
+            Error::FeesExceedPrice => "Fees exceed the sale price",
+            Error::BreedWithSelf => "A kitty cannot breed with itself",
+            Error::Resting => "Kitty is still resting",
+            Error::NotOwnerOfParent => "You must own a parent to breed",
+            Error::NotOwnerOfBothParents => "You must own both parents to breed",
+            Error::NotASire => "This kitty is not available as a sire",
+            Error::SireFeeTooHigh => "Sire fee is too high",
+            Error::InAuction => "Kitty is in an auction",
+            Error::NotInAuction => "Kitty is not in an auction",
+            Error::ZeroDuration => "Auction duration must not be zero",
+            Error::StartBelowEndPrice => "Start price must not be below the end price",
+            Error::BidOnOwnKitty => "You can't bid on your own kitty",
+            Error::BidBelowStart => "Bid is below the starting price",
+            Error::BidTooLow => "Bid is not higher than the current bid",
+            Error::AuctionNotOver => "Auction has not ended yet",
+            Error::AuctionOver => "Auction has ended",
+            Error::OfferOnOwnKitty => "You can't make an offer on your own kitty",
+            Error::ZeroOffer => "Offer must not be zero",
+            Error::NoOffer => "No offer from this buyer",
+            Error::NoOfferToWithdraw => "You have no offer on this kitty",
+            Error::Locked => "Kitty is locked",
+            Error::NotLocked => "Kitty is not locked",
+            Error::Paused => "Module is paused",
+            Error::Infertile => "Kitty can no longer breed",
+            Error::AlreadyClaimed => "You have already claimed a free kitty",
+            Error::PriceBelowMinimum => "Price below minimum",
+            Error::PriceAboveMaximum => "Price above maximum",
+            Error::SwapWithSelf => "Cannot swap with yourself",
+            Error::NoSwap => "No such swap proposal",
+            Error::NotCounterparty => "You are not the counterparty of this swap",
+            Error::SwapStale => "A kitty in this swap has changed hands",
+            Error::Gen0Limit => "Generation 0 supply reached",
+            Error::DnaExists => "DNA already exists",
+            Error::NameRecentlyChanged => "Name recently changed",
+            Error::InconsistentStorage => "Kitty storage is inconsistent",
+            Error::GenerationTooLow => "Kitty generation too low",
+            Error::InvalidRecipient => "Recipient cannot hold kitties",
+            Error::BreedingPaused => "Breeding is paused",
+            Error::NoGift => "Kitty is not a gift",
+            Error::NotGiftRecipient => "This gift is for someone else",
+            Error::NotGiftGiver => "Only the giver can reclaim a gift",
+            Error::GiftHeld => "Kitty is held as a gift",
+            Error::TransferredTooRecently => "Kitty transferred too recently",
+            Error::NotMinter => "Not allowed to create kitties",
+            Error::RecipientNotPermitted => "Recipient not permitted",
+            Error::NoTreasury => "No treasury has been set",
+            Error::TreasuryTooLow => "Not enough free balance in the treasury",
+            Error::TooTired => "Kitty is too tired",
+            Error::Leased => "Kitty is leased out",
+            Error::LeaseToSelf => "Cannot lease a kitty to yourself",
+            Error::ZeroLeaseDuration => "Lease duration must not be zero",
+            Error::NoLeaseOffer => "No lease has been offered to you for this kitty",
+            Error::MaxGenerationReached => "Maximum generation reached",
+            Error::ZeroShares => "Must transfer at least one share",
+            Error::NotEnoughShares => "Not enough shares in this kitty",
+            Error::OwnerNeedsMajority => "Owner must keep a majority of the shares",
+            Error::TooManyShareholders => "Kitty has too many shareholders",
+            Error::CoOwned => "Kitty has other shareholders",
+            Error::CannotAffordFees => "Not enough free balance to pay the fees",
+            Error::BelowExistentialDeposit => "Payment too small to create the recipient's account",
+            Error::TooManySwaps => "Kitty is in too many open swap proposals",
+            Error::TooManyOffers => "Kitty has too many open offers",
+        }
+    }
+}
+
+impl From<Error> for &'static str {
+    fn from(error: Error) -> &'static str {
+        error.as_str()
+    }
+}
+
+/// An offer by `proposer` to trade `my_kitty` for `counterparty`'s `their_kitty`.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SwapProposal<AccountId, Hash> {
+    proposer: AccountId,
+    my_kitty: Hash,
+    counterparty: AccountId,
+    their_kitty: Hash,
+}
+
+/// Decides the DNA of a kitty born to parents with `dna1` and `dna2`.
+pub trait GeneAlgorithm<Hash> {
+    /// The child's DNA. `seed` is a fresh random hash for this birth.
+    fn combine(dna1: &Hash, dna2: &Hash, seed: &Hash) -> Hash;
+}
+
+/// The original gene splicing: each byte comes from `dna2` where the byte of `seed`
+/// at the same position is even, and from `dna1` otherwise.
+pub struct XorMixGenes;
+
+impl<Hash: AsRef<[u8]> + AsMut<[u8]> + Clone> GeneAlgorithm<Hash> for XorMixGenes {
+    fn combine(dna1: &Hash, dna2: &Hash, seed: &Hash) -> Hash {
+        let mut final_dna = dna1.clone();
+
+        for (i, (dna_2_element, r)) in dna2.as_ref().iter().zip(seed.as_ref().iter()).enumerate() {
+            if r % 2 == 0 {
+                final_dna.as_mut()[i] = *dna_2_element;
+            }
+        }
+
+        final_dna
+    }
+}
+
+/// Lets other modules react to kitties changing hands.
+pub trait OnKittyTransfer<AccountId, Hash> {
+    /// Called once `kitty_id` has moved from `from` to `to`.
+    fn on_transfer(from: &AccountId, to: &AccountId, kitty_id: Hash);
+}
+
+impl<AccountId, Hash> OnKittyTransfer<AccountId, Hash> for () {
+    fn on_transfer(_: &AccountId, _: &AccountId, _: Hash) {}
+}
+
+/// Decides which accounts kitties may be transferred to.
+pub trait AccountValidator<AccountId> {
+    /// Whether `who` may receive a kitty.
+    fn is_valid_recipient(who: &AccountId) -> bool;
+}
+
+/// Lets any account receive kitties.
+impl<AccountId> AccountValidator<AccountId> for () {
+    fn is_valid_recipient(_: &AccountId) -> bool {
+        true
+    }
+}
+
+/// Decides which accounts a regulated chain lets take part in trades, e.g. only those that passed
+/// KYC.
+pub trait TransferRestriction<AccountId> {
+    /// Whether `who` is permitted to receive a kitty.
+    fn can_receive(who: &AccountId) -> bool;
+}
+
+/// Permits every account.
+impl<AccountId> TransferRestriction<AccountId> for () {
+    fn can_receive(_: &AccountId) -> bool {
+        true
+    }
+}
+
+/// The fees a currency charges the sender of a transfer on top of the amount sent.
+pub trait TransferFees<Balance> {
+    /// Charged for a transfer to an account that already exists.
+    fn transfer_fee() -> Balance;
+    /// Charged instead of `transfer_fee` for a transfer that creates its recipient's account.
+    fn creation_fee() -> Balance;
+}
+
+/// For a currency that charges nothing to transfer.
+impl<Balance: Zero> TransferFees<Balance> for () {
+    fn transfer_fee() -> Balance {
+        Zero::zero()
+    }
+    fn creation_fee() -> Balance {
+        Zero::zero()
+    }
+}
+
+/// The fees `balances::Module` is configured with.
+impl<T: balances::Trait> TransferFees<T::Balance> for balances::Module<T> {
+    fn transfer_fee() -> T::Balance {
+        <balances::Module<T>>::transfer_fee()
+    }
+    fn creation_fee() -> T::Balance {
+        <balances::Module<T>>::creation_fee()
+    }
+}
+
+/// How long a parent rests after breeding, depending on its generation.
+pub trait CooldownSchedule {
+    /// The number of blocks a parent of generation `gen` rests for.
+    fn cooldown_for_gen(gen: u64) -> u32;
+}
+
+/// Rests generation 0 parents for `COOLDOWN_BLOCKS`, and twice as long for each
+/// generation after that.
+pub struct DoublingCooldown;
+
+impl CooldownSchedule for DoublingCooldown {
+    fn cooldown_for_gen(gen: u64) -> u32 {
+        let blocks = if gen >= 64 {
+            u64::max_value()
+        } else {
+            COOLDOWN_BLOCKS.saturating_mul(1 << gen)
+        };
+        rstd::cmp::min(blocks, u64::from(u32::max_value())) as u32
+    }
+}
+
+/// The balance type of `T::Currency`, which every price, fee and offer of this module is counted in.
+pub type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+    /// How the DNA of bred kitties is put together.
+    type GeneAlgorithm: GeneAlgorithm<<Self as system::Trait>::Hash>;
+
+    /// Told about every transfer. Use `()` if nothing needs to know.
+    type OnKittyTransfer: OnKittyTransfer<Self::AccountId, <Self as system::Trait>::Hash>;
+
+    /// What sales, bids, offers and fees are paid in. `balances::Module` pays in the native
+    /// currency, but any reservable currency will do.
+    type Currency: ReservableCurrency<Self::AccountId>;
+
+    /// What `Currency` charges on top of each transfer, so payments can be checked up front.
+    type TransferFees: TransferFees<BalanceOf<Self>>;
+
+    /// Checked before every transfer. Use `()` to allow any recipient.
+    type AccountValidator: AccountValidator<Self::AccountId>;
+
+    /// Checked before every transfer or sale, after `AccountValidator`. Use `()` to permit anyone.
+    type TransferGate: TransferRestriction<Self::AccountId>;
+
+    /// How long parents rest after breeding. `DoublingCooldown` is a good start.
+    type CooldownSchedule: CooldownSchedule;
+
+    /// Whether a breeder must own both parents, rather than just one of them.
+    const BREED_REQUIRES_BOTH_PARENTS: bool = false;
+
+    /// The maximum number of kitties `create_kitties` mints in one call.
+    const MAX_BATCH_CREATE: u32 = 10;
+
+    /// The maximum number of kitties `batch_transfer` moves in one call.
+    const MAX_BATCH_TRANSFER: u32 = 10;
+
+    /// The maximum number of kitties `set_prices` lists in one call.
+    const MAX_BATCH_PRICE: u32 = 10;
+
+    /// The maximum number of kitties `burn_kitties` burns in one call.
+    const MAX_BATCH_BURN: u32 = 10;
+
+    /// The most accounts, owner included, that may hold shares in one kitty.
+    const MAX_SHAREHOLDERS: u32 = 10;
+
+    /// The most open swap proposals one kitty may be part of, on either side.
+    const MAX_SWAPS_PER_KITTY: u32 = 10;
+
+    /// The most accounts that may have an open offer on one kitty at a time.
+    const MAX_OFFERS_PER_KITTY: u32 = 10;
+
+    /// The share of each `buy_kitty` sale, in percent, paid to the `FeeCollector`.
+    const MARKETPLACE_FEE_PERCENT: u32 = 0;
+
+    /// The share of each `buy_kitty` sale, in percent, paid to the account that minted the kitty.
+    const ROYALTY_PERCENT: u32 = 0;
+
+    /// The maximum number of kitties that may exist at the same time.
+    const MAX_KITTIES: u64 = u64::max_value();
+
+    /// The maximum number of generation 0 kitties that may ever be minted. Bred kitties don't count.
+    const MAX_GEN0: u64 = u64::max_value();
+
+    /// The maximum number of kitties a single account may own.
+    const MAX_KITTIES_PER_ACCOUNT: u64 = u64::max_value();
+
+    /// The lowest price `set_price` accepts.
+    const MIN_LISTING_PRICE: u64 = 0;
+
+    /// The highest price `set_price` accepts.
+    const MAX_LISTING_PRICE: u64 = u64::max_value();
+
+    /// What minting a generation 0 kitty costs, paid to the `Treasury`.
+    const CREATION_FEE: u64 = 0;
+
+    /// What breeding costs, paid to the `Treasury`. The fee is multiplied by one more than the
+    /// older parent's generation, so breeding gets dearer the further down the line it goes.
+    const BASE_BREED_FEE: u64 = 0;
+
+    /// How many times a kitty may breed in its life.
+    const MAX_BREEDS: u32 = u32::max_value();
+
+    /// The most lapsed listings `on_finalize` takes down in one block. Any more wait for the next.
+    const MAX_EXPIRIES_PER_BLOCK: u32 = 10;
+
+    /// Whether every living kitty must have different DNA, so DNA works as a fingerprint.
+    const REQUIRE_UNIQUE_DNA: bool = false;
+
+    /// The chance, in percent, that a byte of a bred kitty's DNA is replaced with a random one.
+    const MUTATION_RATE_PERCENT: u32 = 0;
+
+    /// How many blocks must pass after `set_name` before the same kitty can be renamed.
+    const NAME_COOLDOWN: u64 = 0;
+
+    /// How many owners `TransferHistory` remembers per kitty. Older entries are dropped first.
+    const MAX_HISTORY: u32 = 16;
+
+    /// How many leading bytes of a `Hash` make up a kitty's DNA. The bytes after these are
+    /// always zero. Anything at least as long as the `Hash` uses all of it.
+    const DNA_BYTES: usize = usize::max_value();
+
+    /// The earliest generation `register_elite` lets into the elite breeding pool.
+    const ELITE_MIN_GEN: u64 = 3;
+
+    /// Identifies this module's own account. See `module_account_id`.
+    const MODULE_ID: [u8; 8] = *b"py/kitty";
+
+    /// For body colour, eye type, eye colour and pattern in turn, the percentage of generation 0
+    /// kitties whose trait is made rare, on top of the ones that roll a rare trait anyway.
+    const RARE_TRAIT_PERCENT: [u32; 4] = [0; 4];
+
+    /// How many blocks must pass after a kitty changes hands before it can change hands again,
+    /// whether by transfer or by sale.
+    const TRANSFER_COOLDOWN: u64 = 0;
+
+    /// How much body colour, eye type, eye colour and pattern in turn count for in `rarity_score`.
+    const TRAIT_RARITY_WEIGHTS: [u32; 4] = [1; 4];
+
+    /// Whether anyone may create generation 0 kitties. Permissioned chains turn this off so that
+    /// only the `Minters` root allows can.
+    const MINTER_GATE_DISABLED: bool = true;
+
+    /// How much energy a fully rested kitty has. Each breeding uses one, and a kitty with none
+    /// left is too tired to breed.
+    const MAX_ENERGY: u32 = u32::max_value();
+
+    /// How many blocks a kitty takes to get one energy back. 14,400 six second blocks is a day.
+    const ENERGY_REGEN_BLOCKS: u64 = 14_400;
+
+    /// The deepest generation breeding may produce.
+    const MAX_GENERATION: u64 = u64::max_value();
 }
 
 decl_event!(
@@ -22,29 +565,201 @@ decl_event!(
     where
         <T as system::Trait>::AccountId,
         <T as system::Trait>::Hash,
-        <T as balances::Trait>::Balance
+        <T as system::Trait>::BlockNumber,
+        Balance = BalanceOf<T>
     {
-        Created(AccountId, Hash),
+        // Owner, kitty id, DNA and how many kitties the owner has now, counting the new one.
+        // Before the count was added this event only had the first three fields.
+        Created(AccountId, Hash, Hash, u64),
         PriceSet(AccountId, Hash, Balance),
         Transferred(AccountId, AccountId, Hash),
         Bought(AccountId, AccountId, Hash, Balance),
+        Burned(AccountId, Hash),
+        FeeCollected(AccountId, Balance),
+        RoyaltyPaid(AccountId, Balance),
+        NameSet(AccountId, Hash),
+        Approval(AccountId, AccountId, Hash),
+        ApprovalCleared(Hash),
+        ApprovalForAll(AccountId, AccountId, bool),
+        SirePriceSet(AccountId, Hash, Balance),
+        AuctionCreated(AccountId, Hash, Balance, BlockNumber),
+        BidPlaced(AccountId, Hash, Balance),
+        AuctionSettled(AccountId, AccountId, Hash, Balance),
+        AuctionEnded(Hash),
+        AuctionReserveNotMet(Hash),
+        DutchAuctionCreated(AccountId, Hash, Balance, Balance, BlockNumber),
+        OfferMade(AccountId, Hash, Balance),
+        OfferWithdrawn(AccountId, Hash),
+        Locked(AccountId, Hash),
+        Unlocked(AccountId, Hash),
+        PauseToggled(bool),
+        Bred(AccountId, Hash, Hash, Hash),
+        FreeKittyClaimed(AccountId, Hash),
+        SwapProposed(AccountId, AccountId, u64),
+        SwapAccepted(u64),
+        SwapCancelled(u64),
+        Watched(AccountId, Hash),
+        Unwatched(AccountId, Hash),
+        ForceTransferred(AccountId, AccountId, Hash),
+        EliteRegistered(Hash),
+        AuctionSettleFailed(Hash),
+        BreedingPauseToggled(bool),
+        GiftOffered(AccountId, AccountId, Hash),
+        GiftClaimed(AccountId, Hash),
+        GiftReclaimed(AccountId, Hash),
+        SaleCancelled(AccountId, Hash),
+        // Owner, kitty id and generation of every new kitty, deposited just before its `Created`
+        Minted(AccountId, Hash, u64),
+        TreasuryWithdrawn(AccountId, Balance),
+        // Owner, lessee, kitty id and fee
+        LeaseOffered(AccountId, AccountId, Hash, Balance),
+        // Owner, lessee, kitty id and the block the lease ends at
+        Leased(AccountId, AccountId, Hash, BlockNumber),
+        // From, to, kitty id and basis points
+        SharesTransferred(AccountId, AccountId, Hash, u32),
+        // How many kitties `migrate_kitties` re-encoded, and how many it couldn't decode
+        KittiesMigrated(u64, u64),
+        KittyMigrationFailed(Hash),
     }
 );
 
 decl_storage! {
     trait Store for Module<T: Trait> as KittyStorage {
-        Kitties get(kitty): map T::Hash => Kitty<T::Hash, T::Balance>;
+        Kitties get(kitty): map T::Hash => Kitty<T::Hash, BalanceOf<T>, T::BlockNumber>;
         KittyOwner get(owner_of): map T::Hash => Option<T::AccountId>;
+        KittyBreeder get(breeder_of): map T::Hash => Option<T::AccountId>;
+        // Generation 0 kitties have no parents and list their own id twice
+        KittyParents get(parents_of): map T::Hash => (T::Hash, T::Hash);
+        // Only kept up to date when the runtime sets `REQUIRE_UNIQUE_DNA`
+        DnaExists get(dna_exists): map T::Hash => bool;
+        // The block a kitty was last renamed in, if it has ever been named
+        NameChangeBlock get(name_changed_at): map T::Hash => Option<T::BlockNumber>;
+        // Who has owned each kitty and since when, oldest first and at most `MAX_HISTORY` long
+        TransferHistory get(transfer_history): map T::Hash => Vec<(T::AccountId, T::BlockNumber)>;
+        // The block a kitty last changed hands in, if it ever has
+        LastTransferBlock get(last_transfer_block): map T::Hash => Option<T::BlockNumber>;
 
         AllKittiesArray get(kitty_by_index): map u64 => T::Hash;
         AllKittiesCount get(all_kitties_count): u64;
         AllKittiesIndex: map T::Hash => u64;
+        // How many kitties are listed with `set_price` right now
+        ForSaleCount get(for_sale_count): u64;
+        // The sum of every price a kitty has ever sold for, saturating at the maximum
+        TotalVolume get(total_volume): BalanceOf<T>;
+        // How many kitties each account has sold and bought with `buy_kitty`
+        SalesCount get(sales_count): map T::AccountId => u64;
+        PurchasesCount get(purchases_count): map T::AccountId => u64;
 
         OwnedKittiesArray get(kitty_of_owner_by_index): map (T::AccountId, u64) => T::Hash;
         OwnedKittiesCount get(owned_kitty_count): map T::AccountId => u64;
         OwnedKittiesIndex: map T::Hash => u64;
 
+        // Every generation 0 kitty ever minted, including ones burned since
+        Gen0Count get(gen0_count): u64;
+        GenerationKitties get(kitty_of_generation_by_index): map (u64, u64) => T::Hash;
+        GenerationCount get(generation_count): map u64 => u64;
+        GenerationIndex: map T::Hash => u64;
+
+        // Kitties each account has bookmarked, kept enumerable the same way as owned kitties.
+        // Burning a kitty leaves it on any watchlists until the watcher removes it.
+        Watchlist get(is_watching): map (T::AccountId, T::Hash) => bool;
+        WatchedKittiesArray get(watched_kitty_by_index): map (T::AccountId, u64) => T::Hash;
+        WatchedKittiesCount get(watched_kitty_count): map T::AccountId => u64;
+        WatchedKittiesIndex: map (T::AccountId, T::Hash) => u64;
+
+        // Accounts that have had their one free kitty from `claim_free_kitty`
+        HasClaimed get(has_claimed): map T::AccountId => bool;
+
+        // The kitty most recently created or bred by each account, so callers can find its id
+        LastCreated get(last_created_by): map T::AccountId => T::Hash;
+
+        KittyCooldown get(kitty_cooldown): map T::Hash => T::BlockNumber;
+        BreedCount get(breed_count): map T::Hash => u32;
+        // A kitty's energy as of `LastEnergyBlock`, when it last bred. See `energy`
+        Energy get(stored_energy): map T::Hash => u32;
+        LastEnergyBlock get(last_energy_block): map T::Hash => Option<T::BlockNumber>;
+        // Locked kitties can't change hands or breed until their owner unlocks them
+        LockedKitties get(is_locked): map T::Hash => bool;
+        // Kitties in the elite breeding pool
+        EliteKitties get(is_elite): map T::Hash => bool;
+
+        // Every breeding ever, as (parent 1, parent 2, child, block), for off-chain readers to page through
+        BreedEvents get(breed_event): map u64 => (T::Hash, T::Hash, T::Hash, T::BlockNumber);
+        BreedEventCount get(breed_event_count): u64;
+
+        // Who each gift held by the module's account is for, and who gave it
+        GiftEscrow get(gift_recipient): map T::Hash => Option<T::AccountId>;
+        GiftGiver get(gift_giver): map T::Hash => Option<T::AccountId>;
+        // Who is renting each leased kitty and the block the lease ends at
+        Leases get(lease): map T::Hash => Option<(T::AccountId, T::BlockNumber)>;
+        // Leases owners have offered but the lessee hasn't taken yet: lessee, duration and fee
+        LeaseOffers get(lease_offer): map T::Hash => Option<(T::AccountId, T::BlockNumber, BalanceOf<T>)>;
+
+        KittyApprovals get(approved_account): map T::Hash => Option<T::AccountId>;
+        OperatorApprovals get(is_approved_for_all): map (T::AccountId, T::AccountId) => bool;
+
+        // The block at which a fixed price listing lapses, if it was given one
+        ListingExpiry get(listing_expiry): map T::Hash => Option<T::BlockNumber>;
+        // Kitties to take off the market at the end of each block. Entries can be stale if the
+        // listing was changed since, so they are checked against `ListingExpiry` first
+        ListingExpiryQueue: map T::BlockNumber => Vec<T::Hash>;
+        SireListings get(sire_price): map T::Hash => Option<BalanceOf<T>>;
+        Auctions get(auction): map T::Hash => Option<Auction<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+        DutchAuctions get(dutch_auction): map T::Hash => Option<DutchAuction<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+        SwapProposals get(swap_proposal): map u64 => Option<SwapProposal<T::AccountId, T::Hash>>;
+        NextSwapId get(next_swap_id): u64;
+        // The open swap proposals each kitty is part of, so they can be cleared with it
+        KittySwaps get(swaps_of): map T::Hash => Vec<u64>;
+
+        // What each buyer has reserved to bid on a kitty outside of any listing
+        Offers get(offer): map (T::Hash, T::AccountId) => BalanceOf<T>;
+        // Everyone with an open offer on a kitty, so the best one can be found again
+        Offerers get(offerers_of): map T::Hash => Vec<T::AccountId>;
+        BestOffer get(best_offer): map T::Hash => Option<(T::AccountId, BalanceOf<T>)>;
+
+        // Co-owned kitties, in basis points adding up to `TOTAL_SHARES`. A kitty nobody has
+        // bought into has no entries here, and its owner holds every share. See `shares_of`
+        KittyShares get(kitty_shares): map (T::Hash, T::AccountId) => u32;
+        // Everyone, owner included, holding shares in a co-owned kitty
+        Shareholders get(shareholders): map T::Hash => Vec<T::AccountId>;
+
+        FeeCollector get(fee_collector): Option<T::AccountId>;
+        // Set by root to stop every signed call while an incident is dealt with
+        Paused get(paused): bool;
+        // Like `Paused`, but only stops breeding
+        BreedingPaused get(breeding_paused): bool;
+        // Where creation fees go. Until root sets it, creating kitties is free
+        Treasury get(treasury): Option<T::AccountId>;
+        // Who may create generation 0 kitties while the minter gate is on
+        Minters get(is_minter): map T::AccountId => bool;
+
         Nonce: u64;
+
+        // Which `STORAGE_VERSION` the stored kitties are laid out in
+        StorageVersion get(storage_version): u32;
+    }
+    add_extra_genesis {
+        config(kitties): Vec<(T::AccountId, T::Hash)>;
+
+        build(|storage: &mut runtime_primitives::StorageOverlay, _: &mut runtime_primitives::ChildrenStorageOverlay, config: &GenesisConfig<T>| {
+            runtime_io::with_storage(storage, || {
+                for &(ref owner, hash) in &config.kitties {
+                    let kitty = Kitty {
+                        id: hash,
+                        dna: hash,
+                        price: <BalanceOf<T> as As<u64>>::sa(0),
+                        gen: 0,
+                        name: Vec::new(),
+                        birth_block: Zero::zero(),
+                        for_sale: false,
+                    };
+
+                    <Module<T>>::do_mint(owner, hash, kitty)
+                        .expect("genesis kitties must be mintable");
+                }
+                <StorageVersion<T>>::put(STORAGE_VERSION);
+            });
+        });
     }
 }
 
@@ -53,211 +768,6447 @@ decl_module! {
 
         fn deposit_event<T>() = default;
 
+        fn on_initialize(_n: T::BlockNumber) {
+            if Self::storage_version() < STORAGE_VERSION {
+                Self::migrate_kitties();
+            }
+        }
+
+        fn on_finalize(n: T::BlockNumber) {
+            Self::expire_listings(n);
+        }
+
         fn create_kitty(origin) -> Result {
             let sender = ensure_signed(origin)?;
-            let nonce = <Nonce<T>>::get();
-            let random_hash = (<system::Module<T>>::random_seed(), &sender, nonce)
-                .using_encoded(<T as system::Trait>::Hashing::hash);
+            ensure!(!Self::paused(), Error::Paused.as_str());
+            ensure!(T::MINTER_GATE_DISABLED || Self::is_minter(&sender), Error::NotMinter.as_str());
 
-            let new_kitty = Kitty {
-                id: random_hash,
-                dna: random_hash,
-                price: <T::Balance as As<u64>>::sa(0),
-                gen: 0,
-            };
+            let kitties = Self::prepare_gen0_kitties(&sender, 1)?;
+            Self::pay_creation_fee(&sender, 1)?;
 
-            Self::mint(sender, random_hash, new_kitty)?;
+            Self::mint_gen0_kitties(&sender, kitties)?;
 
-            <Nonce<T>>::mutate(|n| *n += 1);
+            Ok(())
+        }
+
+        fn create_kitties(origin, count: u32) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+            ensure!(T::MINTER_GATE_DISABLED || Self::is_minter(&sender), Error::NotMinter.as_str());
+
+            ensure!(count <= T::MAX_BATCH_CREATE, Error::BatchTooLarge.as_str());
+
+            // Nothing is rolled back if a mint fails half way, so make sure the whole batch fits first
+            let kitties = Self::prepare_gen0_kitties(&sender, count)?;
+            Self::pay_creation_fee(&sender, count)?;
+
+            Self::mint_gen0_kitties(&sender, kitties)?;
 
             Ok(())
         }
 
-        fn set_price(origin, kitty_id: T::Hash, new_price: T::Balance) -> Result {
+        /// Mints a free generation 0 kitty, once per account and without the creation fee.
+        fn claim_free_kitty(origin) -> Result {
             let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+            ensure!(T::MINTER_GATE_DISABLED || Self::is_minter(&sender), Error::NotMinter.as_str());
 
-            ensure!(<Kitties<T>>::exists(kitty_id), "This cat does not exist");
+            ensure!(!Self::has_claimed(&sender), Error::AlreadyClaimed.as_str());
 
-            let owner = Self::owner_of(kitty_id).ok_or("No owner for this kitty")?;
-            ensure!(owner == sender, "You do not own this cat");
+            let kitties = Self::prepare_gen0_kitties(&sender, 1)?;
+            let kitty_id = kitties[0].0;
+            Self::mint_gen0_kitties(&sender, kitties)?;
+            <HasClaimed<T>>::insert(&sender, true);
 
-            let mut kitty = Self::kitty(kitty_id);
-            kitty.price = new_price;
+            Self::deposit_event(RawEvent::FreeKittyClaimed(sender, kitty_id));
 
-            <Kitties<T>>::insert(kitty_id, kitty);
+            Ok(())
+        }
+
+        /// Lists `kitty_id` for `new_price`. The listing lapses `expires_in` blocks from now,
+        /// or never if `expires_in` is zero.
+        fn set_price(origin, kitty_id: T::Hash, new_price: BalanceOf<T>, expires_in: T::BlockNumber) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
 
-            Self::deposit_event(RawEvent::PriceSet(sender, kitty_id, new_price));
+            Self::ensure_can_set_price(&sender, kitty_id, new_price)?;
+            Self::list_for_sale(sender, kitty_id, new_price, expires_in)?;
 
             Ok(())
         }
 
-        fn transfer(origin, to: T::AccountId, kitty_id: T::Hash) -> Result {
+        /// Lists every kitty in `items` at its paired price, with no expiry. Nothing is listed
+        /// unless every kitty could be.
+        fn set_prices(origin, items: Vec<(T::Hash, BalanceOf<T>)>) -> Result {
             let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
 
-            let owner = Self::owner_of(kitty_id).ok_or("No owner for this kitty")?;
-            ensure!(owner == sender, "You do not own this kitty");
+            ensure!(items.len() <= T::MAX_BATCH_PRICE as usize, Error::BatchTooLarge.as_str());
+
+            for &(kitty_id, new_price) in items.iter() {
+                Self::ensure_can_set_price(&sender, kitty_id, new_price)?;
+            }
 
-            Self::transfer_from(sender, to, kitty_id)?;
+            for (kitty_id, new_price) in items {
+                // Without an expiry there is nothing left that can fail
+                Self::list_for_sale(sender.clone(), kitty_id, new_price, Zero::zero())?;
+            }
 
             Ok(())
         }
 
-         fn buy_kitty(origin, kitty_id: T::Hash, max_price: T::Balance) -> Result {
+        fn cancel_sale(origin, kitty_id: T::Hash) -> Result {
             let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
 
-            ensure!(<Kitties<T>>::exists(kitty_id), "This cat does not exist");
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
 
-            let owner = Self::owner_of(kitty_id).ok_or("No owner for this kitty")?;
-            ensure!(owner == sender, "You do not own this kitty");
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
 
             let mut kitty = Self::kitty(kitty_id);
+            ensure!(kitty.for_sale, Error::NotForSale.as_str());
+            kitty.for_sale = false;
+            Self::note_delisted();
 
-            // Get the `kitty_price` and check that it is not zero
-            //      HINT:  `runtime_primitives::traits::Zero` allows you to call `kitty_price.is_zero()` which returns a bool
-            let kitty_price = kitty.price;
-            ensure!(!kitty_price.is_zero(), "kitty price is zero");
+            <Kitties<T>>::insert(kitty_id, kitty);
+            <ListingExpiry<T>>::remove(kitty_id);
 
-            // Check `kitty_price` is less than or equal to max_price
-            ensure!(kitty_price <= max_price, "kitty is too expensive");
-
-            // Use the `Balances` module's `Currency` trait and `transfer()` function to safely transfer funds
-            <balances::Module<T> as Currency<_>>::transfer(&sender, &owner, kitty.price)?;
-
-            // Transfer the kitty using `tranfer_from()` including a proof of why it cannot fail
-            Self::transfer_from(owner.clone(), sender.clone(), kitty_id)
-                .expect("`owner` is shown to own the kitty; \
-                `owner` must have greater than 0 kitties, so transfer cannot cause underflow; \
-                `all_kitty_count` shares the same type as `owned_kitty_count` \
-                and minting ensure there won't ever be more than `max()` kitties, \
-                which means transfer cannot cause an overflow; \
-                qed");
+            Self::deposit_event(RawEvent::SaleCancelled(sender, kitty_id));
+
+            Ok(())
+        }
+
+        fn set_name(origin, kitty_id: T::Hash, name: Vec<u8>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+
+            ensure!(name.len() <= MAX_NAME_LENGTH, Error::NameTooLong.as_str());
+
+            let now = <system::Module<T>>::block_number();
+            if let Some(changed_at) = Self::name_changed_at(kitty_id) {
+                let cooldown = <T::BlockNumber as As<u64>>::sa(T::NAME_COOLDOWN);
+                ensure!(now >= changed_at.saturating_add(cooldown), Error::NameRecentlyChanged.as_str());
+            }
+
+            let mut kitty = Self::kitty(kitty_id);
+            kitty.name = name;
 
-            // Reset kitty price back to zero, and update the storage
-            kitty.price = <T::Balance as As<u64>>::sa(0);
             <Kitties<T>>::insert(kitty_id, kitty);
+            <NameChangeBlock<T>>::insert(kitty_id, now);
+
+            Self::deposit_event(RawEvent::NameSet(sender, kitty_id));
 
-            // Create an event for the cat being bought with relevant details
-            Self::deposit_event(RawEvent::Bought(sender, owner, kitty_id, kitty_price));
             Ok(())
         }
 
-        fn breed_kitty(origin, kitty_id_1: T::Hash, kitty_id_2: T::Hash) -> Result{
+        fn transfer(origin, to: T::AccountId, kitty_id: T::Hash) -> Result {
             let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
 
-            // Check both kitty 1 and kitty 2 "exists"
-            ensure!(<Kitties<T>>::exists(kitty_id_1), "Cat 1 does not exist");
-            ensure!(<Kitties<T>>::exists(kitty_id_2), "Cat 2 does not exist");
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
 
-            // Generate a `random_hash` using the <Nonce<T>>
-            let nonce = <Nonce<T>>::get();
-            let random_hash = (<system::Module<T>>::random_seed(), &sender, nonce)
-                .using_encoded(<T as system::Trait>::Hashing::hash);
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner != to, Error::TransferToSelf.as_str());
+            ensure!(Self::can_transfer(&sender, &owner, kitty_id), Error::NotOwner.as_str());
 
-            let kitty_1 = Self::kitty(kitty_id_1);
-            let kitty_2 = Self::kitty(kitty_id_2);
+            Self::transfer_from(owner, to, kitty_id)?;
 
-            // Our gene splicing algorithm, feel free to make it your own
-            let mut final_dna = kitty_1.dna;
+            Ok(())
+        }
 
-            for (i, (dna_2_element, r)) in kitty_2.dna.as_ref().iter().zip(random_hash.as_ref().iter()).enumerate() {
-                if r % 2 == 0 {
-                    final_dna.as_mut()[i] = *dna_2_element;
-                }
-            }
+        fn batch_transfer(origin, to: T::AccountId, kitty_ids: Vec<T::Hash>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
 
-            // Create a `new_kitty` using: 
-            //      - `random_hash` as `id`
-            //      - `final_dna` as `dna`
-            //      - 0 as `price`
-            //      - the max of the parent's `gen` + 1
-            //          - Hint: `rstd::cmp::max(1, 5) + 1` is `6`
-            let new_kitty = Kitty {
-                id: random_hash,
-                dna: final_dna,
-                price: <T::Balance as As<u64>>::sa(0),
-                gen: rstd::cmp::max(kitty_1.gen, kitty_2.gen) + 1,
-            };
+            ensure!(kitty_ids.len() <= T::MAX_BATCH_TRANSFER as usize, Error::BatchTooLarge.as_str());
+            ensure!(sender != to, Error::TransferToSelf.as_str());
 
-            // `mint()` your new kitty
-            Self::mint(sender, random_hash, new_kitty)?;
+            // Validate the whole batch before moving anything, so it either all goes through or none of it does
+            for (i, kitty_id) in kitty_ids.iter().enumerate() {
+                ensure!(!kitty_ids[..i].contains(kitty_id), Error::DuplicateInBatch.as_str());
+                Self::ensure_can_transfer_from(&sender, &to, *kitty_id)?;
+            }
+            let new_owned_kitty_count_to = Self::owned_kitty_count(&to).checked_add(kitty_ids.len() as u64)
+                .ok_or(Error::Overflow)?;
+            ensure!(new_owned_kitty_count_to <= T::MAX_KITTIES_PER_ACCOUNT, Error::RecipientKittyLimit.as_str());
 
-            <Nonce<T>>::mutate(|n| *n += 1);
+            for kitty_id in kitty_ids {
+                Self::transfer_from(sender.clone(), to.clone(), kitty_id)?;
+            }
 
             Ok(())
         }
-    }
-}
 
-impl<T: Trait> Module<T> {
-    fn mint(to: T::AccountId, kitty_id: T::Hash, new_kitty: Kitty<T::Hash, T::Balance>) -> Result {
-        ensure!(!<KittyOwner<T>>::exists(kitty_id), "Kitty already exists");
+        fn transfer_from_approved(origin, from: T::AccountId, to: T::AccountId, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
 
-        let owned_kitty_count = Self::owned_kitty_count(&to);
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
 
-        let new_owned_kitty_count = owned_kitty_count.checked_add(1)
-            .ok_or("Overflow adding a new kitty to account balance")?;
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == from, Error::FromNotOwner.as_str());
+            ensure!(from != to, Error::TransferToSelf.as_str());
+            ensure!(Self::can_transfer(&sender, &owner, kitty_id), Error::NotApproved.as_str());
 
-        let all_kitties_count = Self::all_kitties_count();
+            Self::transfer_from(from, to, kitty_id)?;
 
-        let new_all_kitties_count = all_kitties_count.checked_add(1)
-            .ok_or("Overflow adding a new kitty to total supply")?;
+            Ok(())
+        }
 
-        <Kitties<T>>::insert(kitty_id, new_kitty);
-        <KittyOwner<T>>::insert(kitty_id, &to);
+        fn approve(origin, to: T::AccountId, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
 
-        <AllKittiesArray<T>>::insert(all_kitties_count, kitty_id);
-        <AllKittiesCount<T>>::put(new_all_kitties_count);
-        <AllKittiesIndex<T>>::insert(kitty_id, all_kitties_count);
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
 
-        <OwnedKittiesArray<T>>::insert((to.clone(), owned_kitty_count), kitty_id);
-        <OwnedKittiesCount<T>>::insert(&to, new_owned_kitty_count);
-        <OwnedKittiesIndex<T>>::insert(kitty_id, owned_kitty_count);
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+            ensure!(to != sender, Error::ApproveSelf.as_str());
 
-        Self::deposit_event(RawEvent::Created(to, kitty_id));
+            <KittyApprovals<T>>::insert(kitty_id, &to);
 
-        Ok(())
-    }
+            Self::deposit_event(RawEvent::Approval(sender, to, kitty_id));
 
-    fn transfer_from(from: T::AccountId, to: T::AccountId, kitty_id: T::Hash) -> Result {
-        let owner = Self::owner_of(kitty_id).ok_or("No owner for this kitty")?;
-            ensure!(owner == from, "You do not own this kitty");
+            Ok(())
+        }
 
-        let owned_kitty_count_from = Self::owned_kitty_count(&from);
-        let owned_kitty_count_to = Self::owned_kitty_count(&to);
+        fn set_approval_for_all(origin, operator: T::AccountId, approved: bool) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
 
-        // Used `checked_add()` to increment the `owned_kitty_count_to` by one into `new_owned_kitty_count_to`
-        let new_owned_kitty_count_to = owned_kitty_count_to.checked_add(1).ok_or("Overflow adding a new kitty to account balance")?;
-        // Used `checked_sub()` to increment the `owned_kitty_count_from` by one into `new_owned_kitty_count_from`
-        //      - Return an `Err()` if overflow or underflow
-        let new_owned_kitty_count_from = owned_kitty_count_from.checked_sub(1).ok_or("Overflow removing a new kitty from account balance")?;
+            ensure!(operator != sender, Error::ApproveSelf.as_str());
 
-        // "Swap and pop"
-        // We our convenience storage items to help simplify removing an element from the OwnedKittiesArray
-        // We switch the last element of OwnedKittiesArray with the element we want to remove
-        let kitty_index = <OwnedKittiesIndex<T>>::get(kitty_id);
-        if kitty_index != new_owned_kitty_count_from {
-            let last_kitty_id = <OwnedKittiesArray<T>>::get((from.clone(), new_owned_kitty_count_from));
-            <OwnedKittiesArray<T>>::insert((from.clone(), kitty_index), last_kitty_id);
-            <OwnedKittiesIndex<T>>::insert(last_kitty_id, kitty_index);
+            if approved {
+                <OperatorApprovals<T>>::insert((sender.clone(), operator.clone()), true);
+            } else {
+                <OperatorApprovals<T>>::remove((sender.clone(), operator.clone()));
+            }
+
+            Self::deposit_event(RawEvent::ApprovalForAll(sender, operator, approved));
+
+            Ok(())
         }
-        
-        // Update KittyOwner for `kitty_id`
-        <KittyOwner<T>>::insert(kitty_id, &to);
-        // Update OwnedKittiesIndex for `kitty_id`
-        <OwnedKittiesIndex<T>>::insert(kitty_id, owned_kitty_count_to);
 
-        // Update OwnedKittiesArray to remove the element from `from`, and add an element to `to`
-        //      - HINT: The last element in OwnedKittiesArray(from) is `new_owned_kitty_count_from`
-        //              The last element in OwnedKittiesArray(to) is `owned_kitty_count_to`
-        <OwnedKittiesArray<T>>::insert((to.clone(), owned_kitty_count_to), kitty_id);
-        <OwnedKittiesArray<T>>::remove((from.clone(), new_owned_kitty_count_from));
+         fn buy_kitty(origin, kitty_id: T::Hash, max_price: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
 
-        // Update the OwnedKittiesCount for `from` and `to`
-        <OwnedKittiesCount<T>>::insert(&from, new_owned_kitty_count_from);
-        <OwnedKittiesCount<T>>::insert(&to, new_owned_kitty_count_to);
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
 
-        Self::deposit_event(RawEvent::Transferred(from, to, kitty_id));
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner != sender, Error::BuyOwnKitty.as_str());
+
+            let kitty = Self::kitty(kitty_id);
+
+            // Only listed kitties can be bought, whatever their price
+            ensure!(kitty.for_sale, Error::NotForSale.as_str());
+            if let Some(expiry) = Self::listing_expiry(kitty_id) {
+                ensure!(<system::Module<T>>::block_number() < expiry, Error::ListingExpired.as_str());
+            }
+            let kitty_price = kitty.price;
+
+            // Check `kitty_price` is less than or equal to max_price
+            ensure!(kitty_price <= max_price, Error::TooExpensive.as_str());
+
+            // Make sure the kitty can actually change hands before any money does
+            Self::ensure_can_transfer_from(&owner, &sender, kitty_id)?;
+            let new_sales_count = Self::sales_count(&owner).checked_add(1).ok_or(Error::Overflow)?;
+            let new_purchases_count = Self::purchases_count(&sender).checked_add(1).ok_or(Error::Overflow)?;
+
+            // Use the runtime's `Currency` to pay the seller, marketplace and breeder
+            Self::pay_for_kitty(&sender, &owner, kitty_id, kitty_price)?;
+
+            // Transfer the kitty, which can no longer fail
+            Self::hand_over_sold_kitty(owner.clone(), sender.clone(), kitty_id);
+            <SalesCount<T>>::insert(&owner, new_sales_count);
+            <PurchasesCount<T>>::insert(&sender, new_purchases_count);
+
+            // Create an event for the cat being bought with relevant details
+            Self::deposit_event(RawEvent::Bought(sender, owner, kitty_id, kitty_price));
+            Ok(())
+        }
+
+        fn set_fee_collector(origin, collector: T::AccountId) -> Result {
+            ensure_root(origin)?;
+
+            <FeeCollector<T>>::put(collector);
+
+            Ok(())
+        }
+
+        fn set_treasury(origin, treasury: T::AccountId) -> Result {
+            ensure_root(origin)?;
+
+            <Treasury<T>>::put(treasury);
+
+            Ok(())
+        }
+
+        /// Pays `amount` of the fees the treasury has collected out to `to`.
+        fn withdraw_treasury(origin, to: T::AccountId, amount: BalanceOf<T>) -> Result {
+            ensure_root(origin)?;
+
+            let treasury = Self::treasury().ok_or(Error::NoTreasury)?;
+            ensure!(
+                <T::Currency as Currency<_>>::free_balance(&treasury) >= amount,
+                Error::TreasuryTooLow.as_str()
+            );
+
+            Self::pay(&treasury, &to, amount)?;
+
+            Self::deposit_event(RawEvent::TreasuryWithdrawn(to, amount));
+
+            Ok(())
+        }
+
+        /// Allows or stops `who` creating generation 0 kitties, unless `MINTER_GATE_DISABLED`
+        /// lets everyone create them.
+        fn set_minter(origin, who: T::AccountId, allowed: bool) -> Result {
+            ensure_root(origin)?;
+
+            if allowed {
+                <Minters<T>>::insert(&who, true);
+            } else {
+                <Minters<T>>::remove(&who);
+            }
+
+            Ok(())
+        }
+
+        fn set_paused(origin, paused: bool) -> Result {
+            ensure_root(origin)?;
+
+            <Paused<T>>::put(paused);
+
+            Self::deposit_event(RawEvent::PauseToggled(paused));
+
+            Ok(())
+        }
+
+        fn set_breeding_paused(origin, paused: bool) -> Result {
+            ensure_root(origin)?;
+
+            <BreedingPaused<T>>::put(paused);
+
+            Self::deposit_event(RawEvent::BreedingPauseToggled(paused));
+
+            Ok(())
+        }
+
+        /// Moves `kitty_id` to `new_owner` whoever owns it now, for sorting out disputes and
+        /// compromised accounts. Anything the owner has done to hold the kitty in place is
+        /// undone first: its lock, lease, listing, auctions, gift, swap proposals and approval
+        /// all go, and any high bid is handed back.
+        fn force_transfer(origin, kitty_id: T::Hash, new_owner: T::AccountId) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner != new_owner, Error::TransferToSelf.as_str());
+            Self::ensure_can_hand_over(&owner, &new_owner, kitty_id)?;
+
+            <LockedKitties<T>>::remove(kitty_id);
+            <Leases<T>>::remove(kitty_id);
+            <LeaseOffers<T>>::remove(kitty_id);
+            if let Some(auction) = <Auctions<T>>::take(kitty_id) {
+                if let Some(bidder) = auction.high_bidder {
+                    <T::Currency as ReservableCurrency<_>>::unreserve(&bidder, auction.high_bid);
+                }
+                Self::deposit_event(RawEvent::AuctionEnded(kitty_id));
+            }
+            if <DutchAuctions<T>>::exists(kitty_id) {
+                <DutchAuctions<T>>::remove(kitty_id);
+                Self::deposit_event(RawEvent::AuctionEnded(kitty_id));
+            }
+            // A gift is held by the module's account, which root is taking the kitty from
+            <GiftEscrow<T>>::remove(kitty_id);
+            <GiftGiver<T>>::remove(kitty_id);
+            for proposal_id in <KittySwaps<T>>::get(kitty_id) {
+                if let Some(proposal) = <SwapProposals<T>>::take(proposal_id) {
+                    Self::forget_swap(proposal_id, &proposal);
+                    Self::deposit_event(RawEvent::SwapCancelled(proposal_id));
+                }
+            }
+
+            // The listing and approval go with the transfer itself
+            Self::transfer_from(owner.clone(), new_owner.clone(), kitty_id)
+                .expect("`ensure_can_hand_over` has checked every way `transfer_from` can fail \
+                that the owner does not control, and everything the owner controls was just cleared; \
+                qed");
+
+            Self::deposit_event(RawEvent::ForceTransferred(owner, new_owner, kitty_id));
+
+            Ok(())
+        }
+
+        /// Mints a kitty of generation `gen` with the given `dna` to `to`, for migrations and
+        /// airdrops. It still counts towards every supply limit.
+        fn force_mint(origin, to: T::AccountId, dna: T::Hash, gen: u64) -> Result {
+            ensure_root(origin)?;
+
+            Self::ensure_can_mint(&to, 1)?;
+            if gen == 0 {
+                Self::ensure_can_mint_gen0(1)?;
+            }
+
+            let nonce = Self::peek_nonce()?;
+            let kitty_id = Self::generate_random_hash(&to, nonce);
+            Self::ensure_new_kitty(kitty_id, dna, &[])?;
+            Self::next_nonce()?;
+
+            let new_kitty = Kitty {
+                id: kitty_id,
+                dna,
+                price: <BalanceOf<T> as As<u64>>::sa(0),
+                gen,
+                name: Vec::new(),
+                birth_block: Zero::zero(),
+                for_sale: false,
+            };
+
+            Self::mint(to, kitty_id, new_kitty)?;
+
+            Ok(())
+        }
+
+        fn burn_kitty(origin, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            Self::ensure_can_burn(&sender, kitty_id)?;
+
+            Self::burn(sender, kitty_id)?;
+
+            Ok(())
+        }
+
+        fn burn_kitties(origin, kitty_ids: Vec<T::Hash>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(kitty_ids.len() <= T::MAX_BATCH_BURN as usize, Error::BatchTooLarge.as_str());
+
+            // Validate the whole batch before burning anything, so it either all goes or none of it does
+            for (i, kitty_id) in kitty_ids.iter().enumerate() {
+                ensure!(!kitty_ids[..i].contains(kitty_id), Error::DuplicateInBatch.as_str());
+                Self::ensure_can_burn(&sender, *kitty_id)?;
+            }
+
+            for kitty_id in kitty_ids {
+                Self::burn(sender.clone(), kitty_id)?;
+            }
+
+            Ok(())
+        }
+
+        fn breed_kitty(origin, kitty_id_1: T::Hash, kitty_id_2: T::Hash) -> Result{
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+            ensure!(!Self::breeding_paused(), Error::BreedingPaused.as_str());
+
+            // A leased kitty is bred by its lessee, not its owner
+            let owns_1 = Self::current_user(kitty_id_1).map_or(false, |user| user == sender);
+            let owns_2 = Self::current_user(kitty_id_2).map_or(false, |user| user == sender);
+            if T::BREED_REQUIRES_BOTH_PARENTS {
+                ensure!(owns_1 && owns_2, Error::NotOwnerOfBothParents.as_str());
+            } else {
+                ensure!(owns_1 || owns_2, Error::NotOwnerOfParent.as_str());
+            }
+
+            Self::do_breed(sender, kitty_id_1, kitty_id_2, None)?;
+
+            Ok(())
+        }
+
+        /// Auctions `kitty_id` for `duration` blocks. A winning bid below `reserve` doesn't sell the kitty.
+        fn create_auction(
+            origin,
+            kitty_id: T::Hash,
+            start_price: BalanceOf<T>,
+            duration: T::BlockNumber,
+            reserve: BalanceOf<T>
+        ) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+            ensure!(!Self::is_auctioned(kitty_id), Error::InAuction.as_str());
+            ensure!(!Self::kitty(kitty_id).for_sale, Error::ListedForSale.as_str());
+            ensure!(!Self::is_locked(kitty_id), Error::Locked.as_str());
+            ensure!(!Self::is_leased(kitty_id), Error::Leased.as_str());
+            ensure!(!duration.is_zero(), Error::ZeroDuration.as_str());
+
+            let end_block = <system::Module<T>>::block_number().checked_add(&duration)
+                .ok_or(Error::Overflow)?;
+            let auction = Auction {
+                seller: sender.clone(),
+                start_price,
+                high_bidder: None,
+                high_bid: Zero::zero(),
+                reserve,
+                end_block,
+            };
+            <Auctions<T>>::insert(kitty_id, auction);
+
+            Self::deposit_event(RawEvent::AuctionCreated(sender, kitty_id, start_price, end_block));
+
+            Ok(())
+        }
+
+        fn bid(origin, kitty_id: T::Hash, amount: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            let mut auction = Self::auction(kitty_id).ok_or(Error::NotInAuction)?;
+            ensure!(<system::Module<T>>::block_number() < auction.end_block, Error::AuctionOver.as_str());
+            ensure!(sender != auction.seller, Error::BidOnOwnKitty.as_str());
+            ensure!(amount >= auction.start_price, Error::BidBelowStart.as_str());
+            if auction.high_bidder.is_some() {
+                ensure!(amount > auction.high_bid, Error::BidTooLow.as_str());
+            }
+
+            // Hold the new bid before releasing the one it replaces. Raising your own bid only
+            // holds the raise, so it needs no more free balance than that.
+            match auction.high_bidder.take() {
+                Some(ref previous_bidder) if *previous_bidder == sender => {
+                    <T::Currency as ReservableCurrency<_>>::reserve(&sender, amount - auction.high_bid)?;
+                },
+                Some(previous_bidder) => {
+                    <T::Currency as ReservableCurrency<_>>::reserve(&sender, amount)?;
+                    <T::Currency as ReservableCurrency<_>>::unreserve(&previous_bidder, auction.high_bid);
+                },
+                None => <T::Currency as ReservableCurrency<_>>::reserve(&sender, amount)?,
+            }
+
+            auction.high_bidder = Some(sender.clone());
+            auction.high_bid = amount;
+            <Auctions<T>>::insert(kitty_id, auction);
+
+            Self::deposit_event(RawEvent::BidPlaced(sender, kitty_id, amount));
+
+            Ok(())
+        }
+
+        fn settle_auction(origin, kitty_id: T::Hash) -> Result {
+            ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            let auction = Self::auction(kitty_id).ok_or(Error::NotInAuction)?;
+            ensure!(<system::Module<T>>::block_number() >= auction.end_block, Error::AuctionNotOver.as_str());
+
+            <Auctions<T>>::remove(kitty_id);
+
+            match auction.high_bidder {
+                Some(ref winner) if auction.high_bid < auction.reserve => {
+                    // Too low to sell, so release the bid and leave the kitty with the seller
+                    <T::Currency as ReservableCurrency<_>>::unreserve(winner, auction.high_bid);
+
+                    Self::deposit_event(RawEvent::AuctionReserveNotMet(kitty_id));
+                },
+                Some(winner) => {
+                    // The bid goes back to the winner first, so if the sale can't go through
+                    // they keep their money and the kitty stays with the seller, unpaid.
+                    // `pay_for_kitty` checks every payment before making any, so a failed sale
+                    // never leaves the bid part paid out.
+                    <T::Currency as ReservableCurrency<_>>::unreserve(&winner, auction.high_bid);
+
+                    let paid = Self::ensure_can_transfer_from(&auction.seller, &winner, kitty_id)
+                        .and_then(|_| Self::pay_for_kitty(&winner, &auction.seller, kitty_id, auction.high_bid));
+                    if paid.is_err() {
+                        Self::deposit_event(RawEvent::AuctionSettleFailed(kitty_id));
+                        return Ok(());
+                    }
+
+                    Self::hand_over_sold_kitty(auction.seller.clone(), winner.clone(), kitty_id);
+
+                    Self::deposit_event(RawEvent::AuctionSettled(auction.seller, winner, kitty_id, auction.high_bid));
+                },
+                // Nobody bid, so the kitty simply stays with the seller
+                None => Self::deposit_event(RawEvent::AuctionEnded(kitty_id)),
+            }
+
+            Ok(())
+        }
+
+        /// Auctions `kitty_id` at a price that falls from `start_price` to `end_price` over
+        /// `duration` blocks. The first buyer to accept the current price gets the kitty.
+        fn create_dutch_auction(
+            origin,
+            kitty_id: T::Hash,
+            start_price: BalanceOf<T>,
+            end_price: BalanceOf<T>,
+            duration: T::BlockNumber
+        ) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+            ensure!(!Self::is_auctioned(kitty_id), Error::InAuction.as_str());
+            ensure!(!Self::kitty(kitty_id).for_sale, Error::ListedForSale.as_str());
+            ensure!(!Self::is_locked(kitty_id), Error::Locked.as_str());
+            ensure!(!Self::is_leased(kitty_id), Error::Leased.as_str());
+            ensure!(!duration.is_zero(), Error::ZeroDuration.as_str());
+            ensure!(start_price >= end_price, Error::StartBelowEndPrice.as_str());
+
+            let auction = DutchAuction {
+                seller: sender.clone(),
+                start_price,
+                end_price,
+                start_block: <system::Module<T>>::block_number(),
+                duration,
+            };
+            <DutchAuctions<T>>::insert(kitty_id, auction);
+
+            Self::deposit_event(RawEvent::DutchAuctionCreated(sender, kitty_id, start_price, end_price, duration));
+
+            Ok(())
+        }
+
+        fn buy_dutch(origin, kitty_id: T::Hash, max_price: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            let auction = Self::dutch_auction(kitty_id).ok_or(Error::NotInAuction)?;
+            ensure!(sender != auction.seller, Error::BuyOwnKitty.as_str());
+
+            let price = Self::current_dutch_price(kitty_id).ok_or(Error::NotInAuction)?;
+            ensure!(price <= max_price, Error::TooExpensive.as_str());
+
+            // The auction itself would block the transfer, so take it down first and put it
+            // back if the sale can't go through
+            <DutchAuctions<T>>::remove(kitty_id);
+            let paid = Self::ensure_can_transfer_from(&auction.seller, &sender, kitty_id)
+                .and_then(|_| Self::pay_for_kitty(&sender, &auction.seller, kitty_id, price));
+            if paid.is_err() {
+                <DutchAuctions<T>>::insert(kitty_id, &auction);
+                return paid;
+            }
+
+            Self::hand_over_sold_kitty(auction.seller.clone(), sender.clone(), kitty_id);
+
+            Self::deposit_event(RawEvent::Bought(sender, auction.seller, kitty_id, price));
+
+            Ok(())
+        }
+
+        fn cancel_dutch_auction(origin, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            let auction = Self::dutch_auction(kitty_id).ok_or(Error::NotInAuction)?;
+            ensure!(auction.seller == sender, Error::NotOwner.as_str());
+
+            <DutchAuctions<T>>::remove(kitty_id);
+
+            Self::deposit_event(RawEvent::AuctionEnded(kitty_id));
+
+            Ok(())
+        }
+
+        /// Offers `amount` for `kitty_id`, listed or not. The amount is reserved until the offer
+        /// is accepted or withdrawn, and a new offer replaces the buyer's previous one. At most
+        /// `MAX_OFFERS_PER_KITTY` buyers may have an offer open on a kitty at once.
+        fn make_offer(origin, kitty_id: T::Hash, amount: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner != sender, Error::OfferOnOwnKitty.as_str());
+            ensure!(!amount.is_zero(), Error::ZeroOffer.as_str());
+            ensure!(!Self::is_leased(kitty_id), Error::Leased.as_str());
+
+            // Only the difference from the offer it replaces is held or released, so raising an
+            // offer needs no more free balance than the raise itself
+            let previous = Self::offer((kitty_id, sender.clone()));
+            if previous.is_zero() {
+                ensure!(
+                    Self::offerers_of(kitty_id).len() < T::MAX_OFFERS_PER_KITTY as usize,
+                    Error::TooManyOffers.as_str()
+                );
+            }
+            if amount > previous {
+                <T::Currency as ReservableCurrency<_>>::reserve(&sender, amount - previous)?;
+            } else {
+                <T::Currency as ReservableCurrency<_>>::unreserve(&sender, previous - amount);
+            }
+
+            <Offers<T>>::insert((kitty_id, sender.clone()), amount);
+            if previous.is_zero() {
+                <Offerers<T>>::mutate(kitty_id, |offerers| offerers.push(sender.clone()));
+            }
+            Self::update_best_offer(kitty_id, &sender);
+
+            Self::deposit_event(RawEvent::OfferMade(sender, kitty_id, amount));
+
+            Ok(())
+        }
+
+        fn withdraw_offer(origin, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            let amount = <Offers<T>>::take((kitty_id, sender.clone()));
+            ensure!(!amount.is_zero(), Error::NoOfferToWithdraw.as_str());
+
+            <T::Currency as ReservableCurrency<_>>::unreserve(&sender, amount);
+            Self::forget_offer(kitty_id, &sender);
+
+            Self::deposit_event(RawEvent::OfferWithdrawn(sender, kitty_id));
+
+            Ok(())
+        }
+
+        fn accept_offer(origin, kitty_id: T::Hash, buyer: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+
+            let amount = Self::offer((kitty_id, buyer.clone()));
+            ensure!(!amount.is_zero(), Error::NoOffer.as_str());
+
+            // Make sure the kitty can actually change hands before any money does
+            Self::ensure_can_transfer_from(&sender, &buyer, kitty_id)?;
+            // The offer is only cancelled once the sale is sure to go through. Releasing it
+            // gives the buyer back `amount` to pay with.
+            let sale = Self::sale_payments(&buyer, &sender, kitty_id, amount)?;
+            ensure!(
+                <T::Currency as Currency<_>>::free_balance(&buyer).saturating_add(amount) >= sale.total,
+                Error::InsufficientBalance.as_str()
+            );
+
+            <Offers<T>>::remove((kitty_id, buyer.clone()));
+            <T::Currency as ReservableCurrency<_>>::unreserve(&buyer, amount);
+            Self::forget_offer(kitty_id, &buyer);
+            Self::make_sale_payments(&buyer, kitty_id, amount, sale)?;
+
+            Self::hand_over_sold_kitty(sender.clone(), buyer.clone(), kitty_id);
+
+            Self::deposit_event(RawEvent::Bought(buyer, sender, kitty_id, amount));
+
+            Ok(())
+        }
+
+        fn lock_kitty(origin, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+            // An auction has to be able to hand the kitty over when it ends
+            ensure!(!Self::is_auctioned(kitty_id), Error::InAuction.as_str());
+
+            <LockedKitties<T>>::insert(kitty_id, true);
+
+            Self::deposit_event(RawEvent::Locked(sender, kitty_id));
+
+            Ok(())
+        }
+
+        fn unlock_kitty(origin, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+            ensure!(Self::is_locked(kitty_id), Error::NotLocked.as_str());
+
+            <LockedKitties<T>>::remove(kitty_id);
+
+            Self::deposit_event(RawEvent::Unlocked(sender, kitty_id));
+
+            Ok(())
+        }
+
+        /// Proposes to trade `my_kitty` for `counterparty`'s `their_kitty`. Nothing moves until
+        /// the counterparty accepts, and neither kitty is held in the meantime.
+        fn propose_swap(origin, my_kitty: T::Hash, their_kitty: T::Hash, counterparty: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(sender != counterparty, Error::SwapWithSelf.as_str());
+            let owner = Self::owner_of(my_kitty).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+            let their_owner = Self::owner_of(their_kitty).ok_or(Error::NoOwner)?;
+            ensure!(their_owner == counterparty, Error::SwapStale.as_str());
+
+            let proposal_id = Self::next_swap_id();
+            let next_swap_id = proposal_id.checked_add(1).ok_or(Error::Overflow)?;
+            for kitty_id in &[my_kitty, their_kitty] {
+                ensure!(
+                    Self::swaps_of(kitty_id).len() < T::MAX_SWAPS_PER_KITTY as usize,
+                    Error::TooManySwaps.as_str()
+                );
+            }
+
+            let proposal = SwapProposal {
+                proposer: sender.clone(),
+                my_kitty,
+                counterparty: counterparty.clone(),
+                their_kitty,
+            };
+            <SwapProposals<T>>::insert(proposal_id, proposal);
+            <NextSwapId<T>>::put(next_swap_id);
+            <KittySwaps<T>>::mutate(my_kitty, |swaps| swaps.push(proposal_id));
+            <KittySwaps<T>>::mutate(their_kitty, |swaps| swaps.push(proposal_id));
+
+            Self::deposit_event(RawEvent::SwapProposed(sender, counterparty, proposal_id));
+
+            Ok(())
+        }
+
+        fn accept_swap(origin, proposal_id: u64) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            let proposal = Self::swap_proposal(proposal_id).ok_or(Error::NoSwap)?;
+            ensure!(proposal.counterparty == sender, Error::NotCounterparty.as_str());
+
+            // Either kitty may have been sold or given away since the proposal was made
+            let my_owner = Self::owner_of(proposal.my_kitty).ok_or(Error::SwapStale)?;
+            let their_owner = Self::owner_of(proposal.their_kitty).ok_or(Error::SwapStale)?;
+            ensure!(my_owner == proposal.proposer && their_owner == sender, Error::SwapStale.as_str());
+
+            // Both halves have to go through, so check them both before moving either
+            Self::ensure_can_transfer_from(&proposal.proposer, &sender, proposal.my_kitty)?;
+            Self::ensure_can_transfer_from(&sender, &proposal.proposer, proposal.their_kitty)?;
+
+            <SwapProposals<T>>::remove(proposal_id);
+            Self::forget_swap(proposal_id, &proposal);
+            Self::transfer_from(proposal.proposer.clone(), sender.clone(), proposal.my_kitty)?;
+            Self::transfer_from(sender, proposal.proposer, proposal.their_kitty)?;
+
+            Self::deposit_event(RawEvent::SwapAccepted(proposal_id));
+
+            Ok(())
+        }
+
+        fn cancel_swap(origin, proposal_id: u64) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            let proposal = Self::swap_proposal(proposal_id).ok_or(Error::NoSwap)?;
+            ensure!(proposal.proposer == sender || proposal.counterparty == sender, Error::NotCounterparty.as_str());
+
+            <SwapProposals<T>>::remove(proposal_id);
+            Self::forget_swap(proposal_id, &proposal);
+
+            Self::deposit_event(RawEvent::SwapCancelled(proposal_id));
+
+            Ok(())
+        }
+
+        fn watch(origin, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            if Self::is_watching((sender.clone(), kitty_id)) {
+                return Ok(());
+            }
+
+            let count = Self::watched_kitty_count(&sender);
+            let new_count = count.checked_add(1).ok_or(Error::Overflow)?;
+
+            <Watchlist<T>>::insert((sender.clone(), kitty_id), true);
+            <WatchedKittiesArray<T>>::insert((sender.clone(), count), kitty_id);
+            <WatchedKittiesCount<T>>::insert(&sender, new_count);
+            <WatchedKittiesIndex<T>>::insert((sender.clone(), kitty_id), count);
+
+            Self::deposit_event(RawEvent::Watched(sender, kitty_id));
+
+            Ok(())
+        }
+
+        /// Removes `kitty_id` from the caller's watchlist. Unwatching a kitty that isn't on it does nothing.
+        fn unwatch(origin, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            if !Self::is_watching((sender.clone(), kitty_id)) {
+                return Ok(());
+            }
+
+            let new_count = Self::watched_kitty_count(&sender).checked_sub(1).ok_or(Error::Overflow)?;
+
+            // "Swap and pop", as for owned kitties
+            let index = <WatchedKittiesIndex<T>>::get((sender.clone(), kitty_id));
+            if index != new_count {
+                let last_kitty_id = <WatchedKittiesArray<T>>::get((sender.clone(), new_count));
+                <WatchedKittiesArray<T>>::insert((sender.clone(), index), last_kitty_id);
+                <WatchedKittiesIndex<T>>::insert((sender.clone(), last_kitty_id), index);
+            }
+            <WatchedKittiesArray<T>>::remove((sender.clone(), new_count));
+            <WatchedKittiesIndex<T>>::remove((sender.clone(), kitty_id));
+            <WatchedKittiesCount<T>>::insert(&sender, new_count);
+            <Watchlist<T>>::remove((sender.clone(), kitty_id));
+
+            Self::deposit_event(RawEvent::Unwatched(sender, kitty_id));
+
+            Ok(())
+        }
+
+        /// Enters `kitty_id` into the elite breeding pool, which only takes kitties of generation
+        /// `ELITE_MIN_GEN` or later. Registering a kitty that is already in the pool does nothing.
+        fn register_elite(origin, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+            ensure!(Self::kitty(kitty_id).gen >= T::ELITE_MIN_GEN, Error::GenerationTooLow.as_str());
+
+            if Self::is_elite(kitty_id) {
+                return Ok(());
+            }
+
+            <EliteKitties<T>>::insert(kitty_id, true);
+
+            Self::deposit_event(RawEvent::EliteRegistered(kitty_id));
+
+            Ok(())
+        }
+
+        /// Hands `kitty_id` to the module's own account, where it stays until root releases it.
+        fn deposit_to_module(origin, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+
+            Self::escrow(sender, kitty_id)?;
+
+            Ok(())
+        }
+
+        /// Moves a kitty held by the module's account to `to`.
+        fn release_from_module(origin, kitty_id: T::Hash, to: T::AccountId) -> Result {
+            ensure_root(origin)?;
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let module_account = Self::module_account_id();
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == module_account, Error::NotOwner.as_str());
+            // Gifts are only ever claimed or reclaimed
+            ensure!(Self::gift_recipient(kitty_id).is_none(), Error::GiftHeld.as_str());
+
+            Self::transfer_from(module_account, to, kitty_id)?;
+
+            Ok(())
+        }
+
+        /// Gives `kitty_id` to `to`, who has to `claim_gift` it. Until then the module's account
+        /// holds the kitty and the giver can take it back with `reclaim_gift`.
+        fn offer_gift(origin, kitty_id: T::Hash, to: T::AccountId) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+            ensure!(sender != to, Error::TransferToSelf.as_str());
+
+            Self::escrow(sender.clone(), kitty_id)?;
+            <GiftEscrow<T>>::insert(kitty_id, to.clone());
+            <GiftGiver<T>>::insert(kitty_id, sender.clone());
+
+            Self::deposit_event(RawEvent::GiftOffered(sender, to, kitty_id));
+
+            Ok(())
+        }
+
+        fn claim_gift(origin, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            let recipient = Self::gift_recipient(kitty_id).ok_or(Error::NoGift)?;
+            ensure!(recipient == sender, Error::NotGiftRecipient.as_str());
+
+            Self::transfer_from(Self::module_account_id(), sender.clone(), kitty_id)?;
+            <GiftEscrow<T>>::remove(kitty_id);
+            <GiftGiver<T>>::remove(kitty_id);
+
+            Self::deposit_event(RawEvent::GiftClaimed(sender, kitty_id));
+
+            Ok(())
+        }
+
+        fn reclaim_gift(origin, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            let giver = Self::gift_giver(kitty_id).ok_or(Error::NoGift)?;
+            ensure!(giver == sender, Error::NotGiftGiver.as_str());
+
+            Self::transfer_from(Self::module_account_id(), sender.clone(), kitty_id)?;
+            <GiftEscrow<T>>::remove(kitty_id);
+            <GiftGiver<T>>::remove(kitty_id);
+
+            Self::deposit_event(RawEvent::GiftReclaimed(sender, kitty_id));
+
+            Ok(())
+        }
+
+        /// Offers to rent `kitty_id` to `lessee` for `duration` blocks. The lease starts once the
+        /// lessee pays `fee` with `accept_lease`, and the kitty can't change hands until it ends.
+        /// Offering again replaces the previous offer.
+        fn lease_kitty(origin, kitty_id: T::Hash, lessee: T::AccountId, duration: T::BlockNumber, fee: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+            ensure!(lessee != sender, Error::LeaseToSelf.as_str());
+            ensure!(!duration.is_zero(), Error::ZeroLeaseDuration.as_str());
+            ensure!(!Self::is_leased(kitty_id), Error::Leased.as_str());
+            Self::ensure_not_on_sale(kitty_id)?;
+
+            <LeaseOffers<T>>::insert(kitty_id, (lessee.clone(), duration, fee));
+
+            Self::deposit_event(RawEvent::LeaseOffered(sender, lessee, kitty_id, fee));
+
+            Ok(())
+        }
+
+        /// Pays the fee for the lease offered to the sender on `kitty_id` and starts it.
+        fn accept_lease(origin, kitty_id: T::Hash) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            let (lessee, duration, fee) = Self::lease_offer(kitty_id).ok_or(Error::NoLeaseOffer)?;
+            ensure!(lessee == sender, Error::NoLeaseOffer.as_str());
+            ensure!(!Self::is_leased(kitty_id), Error::Leased.as_str());
+            // The owner may have put the kitty up for sale since offering the lease
+            Self::ensure_not_on_sale(kitty_id)?;
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            let ends_at = <system::Module<T>>::block_number().checked_add(&duration)
+                .ok_or(Error::Overflow)?;
+
+            Self::pay(&sender, &owner, fee)?;
+
+            // Offers can't be accepted while the kitty is leased, so hand them all back
+            Self::refund_offers(kitty_id);
+            <LeaseOffers<T>>::remove(kitty_id);
+            <Leases<T>>::insert(kitty_id, (sender.clone(), ends_at));
+
+            Self::deposit_event(RawEvent::Leased(owner, sender, kitty_id, ends_at));
+
+            Ok(())
+        }
+
+        /// Gives `bps` of the sender's shares in `kitty_id` to `to`. Shareholders get their cut
+        /// of the price when the kitty is sold, but only its owner can move, sell or breed it.
+        /// The owner always keeps a majority of the shares.
+        fn transfer_shares(origin, kitty_id: T::Hash, to: T::AccountId, bps: u32) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+            ensure!(sender != to, Error::TransferToSelf.as_str());
+            ensure!(bps > 0, Error::ZeroShares.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            let held = Self::shares_of(kitty_id, &sender);
+            let left = held.checked_sub(bps).ok_or(Error::NotEnoughShares)?;
+            if sender == owner {
+                ensure!(left > TOTAL_SHARES / 2, Error::OwnerNeedsMajority.as_str());
+            }
+
+            let mut holders = Self::shareholders(kitty_id);
+            if holders.is_empty() {
+                holders.push(owner.clone());
+                <KittyShares<T>>::insert((kitty_id, owner.clone()), TOTAL_SHARES);
+            }
+            if !holders.contains(&to) {
+                ensure!(holders.len() < T::MAX_SHAREHOLDERS as usize, Error::TooManyShareholders.as_str());
+                holders.push(to.clone());
+            }
+
+            if left == 0 {
+                <KittyShares<T>>::remove((kitty_id, sender.clone()));
+                holders.retain(|holder| *holder != sender);
+            } else {
+                <KittyShares<T>>::insert((kitty_id, sender.clone()), left);
+            }
+            <KittyShares<T>>::mutate((kitty_id, to.clone()), |shares| *shares += bps);
+
+            if holders.len() == 1 {
+                // The owner has every share back
+                Self::clear_shares(kitty_id);
+            } else {
+                <Shareholders<T>>::insert(kitty_id, holders);
+            }
+
+            Self::deposit_event(RawEvent::SharesTransferred(sender, to, kitty_id, bps));
+
+            Ok(())
+        }
+
+        fn set_sire_price(origin, kitty_id: T::Hash, price: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+            ensure!(owner == sender, Error::NotOwner.as_str());
+
+            <SireListings<T>>::insert(kitty_id, price);
+
+            Self::deposit_event(RawEvent::SirePriceSet(sender, kitty_id, price));
+
+            Ok(())
+        }
+
+        fn breed_with_sire(origin, my_kitty: T::Hash, sire: T::Hash, max_fee: BalanceOf<T>) -> Result {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::paused(), Error::Paused.as_str());
+            ensure!(!Self::breeding_paused(), Error::BreedingPaused.as_str());
+
+            let user = Self::current_user(my_kitty).ok_or(Error::NoOwner)?;
+            ensure!(user == sender, Error::NotOwner.as_str());
+
+            let sire_owner = Self::owner_of(sire).ok_or(Error::NoOwner)?;
+            let fee = Self::sire_price(sire).ok_or(Error::NotASire)?;
+            ensure!(fee <= max_fee, Error::SireFeeTooHigh.as_str());
+
+            Self::do_breed(sender, my_kitty, sire, Some((sire_owner, fee)))?;
+
+            Ok(())
+        }
+    }
+}
+
+/// The relative cost of a call, in the same units for every call of this module.
+///
+/// The dispatch of this Substrate version has no notion of weight, so nothing charges by
+/// these yet. They live next to the calls so fees can be wired up to them once it does.
+pub type Weight = u32;
+
+/// Calls that only touch a few storage items of a single account.
+pub const BASE_WEIGHT: Weight = 10_000;
+/// Moving one kitty between two owners.
+pub const TRANSFER_WEIGHT: Weight = 50_000;
+/// Minting one kitty.
+pub const MINT_WEIGHT: Weight = 100_000;
+/// A sale, which moves both a kitty and the payment for it.
+pub const SALE_WEIGHT: Weight = 100_000;
+/// Breeding, which reads both parents before minting the child.
+pub const BREED_WEIGHT: Weight = 200_000;
+
+impl<T: Trait> Call<T> {
+    /// The weight of this call. Batch calls scale with the number of kitties they touch.
+    pub fn weight(&self) -> Weight {
+        match *self {
+            Call::create_kitty(..) | Call::force_mint(..) => MINT_WEIGHT,
+            Call::create_kitties(count) => MINT_WEIGHT.saturating_mul(count),
+            Call::transfer(..)
+                | Call::transfer_from_approved(..)
+                | Call::force_transfer(..)
+                | Call::deposit_to_module(..)
+                | Call::release_from_module(..)
+                | Call::offer_gift(..)
+                | Call::claim_gift(..)
+                | Call::reclaim_gift(..) => TRANSFER_WEIGHT,
+            Call::batch_transfer(_, ref kitty_ids) => TRANSFER_WEIGHT.saturating_mul(kitty_ids.len() as Weight),
+            Call::burn_kitty(..) => TRANSFER_WEIGHT,
+            Call::burn_kitties(ref kitty_ids) => TRANSFER_WEIGHT.saturating_mul(kitty_ids.len() as Weight),
+            Call::set_prices(ref items) => BASE_WEIGHT.saturating_mul(items.len() as Weight),
+            Call::buy_kitty(..)
+                | Call::settle_auction(..)
+                | Call::buy_dutch(..)
+                | Call::accept_offer(..)
+                | Call::accept_lease(..) => SALE_WEIGHT,
+            Call::breed_kitty(..) => BREED_WEIGHT,
+            Call::breed_with_sire(..) => BREED_WEIGHT.saturating_add(SALE_WEIGHT),
+            _ => BASE_WEIGHT,
+        }
+    }
+}
+
+impl<T: Trait> Module<T> {
+    /// All kitties owned by `owner`, in `OwnedKittiesArray` order.
+    pub fn kitties_of(owner: &T::AccountId) -> Vec<T::Hash> {
+        (0..Self::owned_kitty_count(owner))
+            .map(|i| Self::kitty_of_owner_by_index((owner.clone(), i)))
+            .collect()
+    }
+
+    /// `owner`'s kitties with their prices, dearest first. Kitties with the same price keep
+    /// their `kitties_of` order. This reads and sorts every kitty the owner has, so it is meant
+    /// for RPC and off-chain callers, not for dispatchables.
+    pub fn owned_sorted_by_price(owner: &T::AccountId) -> Vec<(T::Hash, BalanceOf<T>)> {
+        let mut priced: Vec<_> = Self::kitties_of(owner)
+            .into_iter()
+            .map(|kitty_id| (kitty_id, Self::kitty(kitty_id).price))
+            .collect();
+        priced.sort_by(|a, b| b.1.cmp(&a.1));
+        priced
+    }
+
+    /// The account this module holds kitties in, made from the hash of `MODULE_ID`. Nobody
+    /// has its key, so only this module can move what it owns.
+    pub fn module_account_id() -> T::AccountId {
+        let hash = <T as system::Trait>::Hashing::hash(&T::MODULE_ID[..]);
+        T::AccountId::decode(&mut hash.as_ref()).unwrap_or_default()
+    }
+
+    /// Up to `limit` kitties from `AllKittiesArray`, starting at index `start`. This is for RPC
+    /// and off-chain callers paging through the whole supply; nothing on chain should use it.
+    pub fn kitties_page(start: u64, limit: u64) -> Vec<T::Hash> {
+        let end = rstd::cmp::min(start.saturating_add(limit), Self::all_kitties_count());
+        (start..end).map(Self::kitty_by_index).collect()
+    }
+
+    /// How many of the `TOTAL_SHARES` in `kitty_id` `who` holds.
+    pub fn shares_of(kitty_id: T::Hash, who: &T::AccountId) -> u32 {
+        if <Shareholders<T>>::exists(kitty_id) {
+            Self::kitty_shares((kitty_id, who.clone()))
+        } else if Self::owner_of(kitty_id).as_ref() == Some(who) {
+            TOTAL_SHARES
+        } else {
+            0
+        }
+    }
+
+    /// Checks `kitty_id` isn't listed or in an auction, which it can't be while it is leased.
+    fn ensure_not_on_sale(kitty_id: T::Hash) -> Result {
+        ensure!(!Self::kitty(kitty_id).for_sale, Error::ListedForSale.as_str());
+        ensure!(!Self::is_auctioned(kitty_id), Error::InAuction.as_str());
+
+        Ok(())
+    }
+
+    /// Whether `kitty_id` is rented out right now.
+    pub fn is_leased(kitty_id: T::Hash) -> bool {
+        match Self::lease(kitty_id) {
+            Some((_, ends_at)) => <system::Module<T>>::block_number() < ends_at,
+            None => false,
+        }
+    }
+
+    /// Who gets to use `kitty_id`: the lessee while a lease is running, otherwise the owner.
+    pub fn current_user(kitty_id: T::Hash) -> Option<T::AccountId> {
+        match Self::lease(kitty_id) {
+            Some((lessee, _)) if Self::is_leased(kitty_id) => Some(lessee),
+            _ => Self::owner_of(kitty_id),
+        }
+    }
+
+    /// The owner of `kitty_id`, or `None` unless the kitty is in `Kitties` as well as `KittyOwner`.
+    pub fn ownership(kitty_id: T::Hash) -> Option<T::AccountId> {
+        if !<Kitties<T>>::exists(kitty_id) {
+            return None;
+        }
+        Self::owner_of(kitty_id)
+    }
+
+    /// Re-encodes every kitty stored as a `KittyV0` in the current layout and moves
+    /// `StorageVersion` up to date. `on_initialize` runs this once, in the first block of the
+    /// upgraded runtime, so the rest of the module never sees an old kitty.
+    ///
+    /// Migrated kitties have no name, and the block the migration runs in as their
+    /// `birth_block` since the real one was never recorded. Version 0 listed any kitty with a
+    /// price, so those come out `for_sale`. Only a kitty whose bytes are exactly a `KittyV0`
+    /// is rewritten: one already in the current layout is left alone, and anything else is
+    /// left as it is and reported in `KittyMigrationFailed`.
+    ///
+    /// Every kitty is migrated in the one block. The rest of the module can't read a
+    /// `KittyV0`, so spreading the work over several blocks would mean refusing every call
+    /// until it was done. The work is bounded by `MAX_KITTIES` instead, which a chain with
+    /// version 0 kitties should keep to what a block can migrate.
+    pub fn migrate_kitties() -> Weight {
+        let now = <system::Module<T>>::block_number();
+        let count = Self::all_kitties_count();
+        let mut migrated = 0u64;
+        let mut failed = 0u64;
+
+        for i in 0..count {
+            let kitty_id = Self::kitty_by_index(i);
+            let raw = support::storage::get_raw(&<Kitties<T>>::key_for(&kitty_id)).unwrap_or_default();
+            match Self::decode_exactly::<KittyV0<T::Hash, BalanceOf<T>>>(&raw) {
+                Some(old) => {
+                    let for_sale = !old.price.is_zero();
+                    <Kitties<T>>::insert(kitty_id, Kitty {
+                        id: old.id,
+                        dna: old.dna,
+                        price: old.price,
+                        gen: old.gen,
+                        name: Vec::new(),
+                        birth_block: now,
+                        for_sale,
+                    });
+                    if for_sale {
+                        <ForSaleCount<T>>::mutate(|listed| *listed = listed.saturating_add(1));
+                    }
+                    migrated += 1;
+                }
+                None if Self::decode_exactly::<Kitty<T::Hash, BalanceOf<T>, T::BlockNumber>>(&raw).is_some() => (),
+                None => {
+                    Self::deposit_event(RawEvent::KittyMigrationFailed(kitty_id));
+                    failed += 1;
+                }
+            }
+        }
+        <StorageVersion<T>>::put(STORAGE_VERSION);
+        Self::deposit_event(RawEvent::KittiesMigrated(migrated, failed));
+
+        BASE_WEIGHT.saturating_mul(count.min(u64::from(Weight::max_value())) as Weight)
+    }
+
+    /// `bytes` decoded as a `V`, as long as they are all used up. Without that check the current
+    /// kitty layout would decode as a `KittyV0`, since it starts with the same fields.
+    fn decode_exactly<V: Decode>(bytes: &[u8]) -> Option<V> {
+        let mut input = bytes;
+        V::decode(&mut input).filter(|_| input.is_empty())
+    }
+
+    /// Checks that every kitty in `AllKittiesArray` is in `Kitties`, has an owner, and sits where
+    /// both the global and its owner's indices say it does. Storage maps can't be iterated, so a
+    /// stray `KittyOwner` entry for a kitty missing from `AllKittiesArray` goes unnoticed.
+    ///
+    /// This reads every kitty, so it is meant for tests, or for a debug build to call from
+    /// `on_initialize` to catch a change that leaves the maps disagreeing with each other.
+    pub fn validate_storage() -> Result {
+        let all_kitties_count = Self::all_kitties_count();
+        for i in 0..all_kitties_count {
+            let kitty_id = Self::kitty_by_index(i);
+            ensure!(<Kitties<T>>::exists(kitty_id), Error::InconsistentStorage.as_str());
+            ensure!(<AllKittiesIndex<T>>::get(kitty_id) == i, Error::InconsistentStorage.as_str());
+
+            let owner = Self::owner_of(kitty_id).ok_or(Error::InconsistentStorage)?;
+            let owned_index = <OwnedKittiesIndex<T>>::get(kitty_id);
+            ensure!(owned_index < Self::owned_kitty_count(&owner), Error::InconsistentStorage.as_str());
+            ensure!(
+                Self::kitty_of_owner_by_index((owner, owned_index)) == kitty_id,
+                Error::InconsistentStorage.as_str()
+            );
+        }
+        ensure!(!<AllKittiesArray<T>>::exists(all_kitties_count), Error::InconsistentStorage.as_str());
+
+        Ok(())
+    }
+
+    /// What `kitty_id` costs right now in its Dutch auction, or `None` if it isn't in one.
+    pub fn current_dutch_price(kitty_id: T::Hash) -> Option<BalanceOf<T>> {
+        let auction = Self::dutch_auction(kitty_id)?;
+
+        let elapsed = <system::Module<T>>::block_number() - auction.start_block;
+        let elapsed = <BalanceOf<T> as As<u64>>::sa(rstd::cmp::min(elapsed, auction.duration).as_());
+        let duration = <BalanceOf<T> as As<u64>>::sa(auction.duration.as_());
+
+        // Multiply before dividing to keep the precision, unless that would overflow
+        let drop = auction.start_price - auction.end_price;
+        let discount = match drop.checked_mul(&elapsed) {
+            Some(scaled) => scaled / duration,
+            None => drop / duration * elapsed,
+        };
+
+        Some(auction.start_price - discount)
+    }
+
+    /// Whether `kitty_id` is in an English or a Dutch auction.
+    fn is_auctioned(kitty_id: T::Hash) -> bool {
+        <Auctions<T>>::exists(kitty_id) || <DutchAuctions<T>>::exists(kitty_id)
+    }
+
+    /// How `kitty_id` can be bought or bred with right now. A kitty in an auction can't be on
+    /// any other sale, and one that is both listed and offered as a sire shows as `FixedPrice`.
+    pub fn sale_status(kitty_id: T::Hash) -> SaleStatus<BalanceOf<T>, T::BlockNumber> {
+        if let Some(auction) = Self::auction(kitty_id) {
+            return SaleStatus::Auction(auction.end_block);
+        }
+        if let Some(auction) = Self::dutch_auction(kitty_id) {
+            return SaleStatus::Auction(auction.start_block.saturating_add(auction.duration));
+        }
+
+        let kitty = Self::kitty(kitty_id);
+        if kitty.for_sale {
+            return SaleStatus::FixedPrice(kitty.price);
+        }
+
+        match Self::sire_price(kitty_id) {
+            Some(price) => SaleStatus::Sire(price),
+            None => SaleStatus::NotForSale,
+        }
+    }
+
+    /// `kitty_id` together with its owner, approval and sale state, or `None` if it doesn't exist.
+    pub fn kitty_info(kitty_id: T::Hash) -> Option<KittyInfo<T::AccountId, T::Hash, BalanceOf<T>, T::BlockNumber>> {
+        let owner = Self::owner_of(kitty_id)?;
+        let kitty = Self::kitty(kitty_id);
+
+        Some(KittyInfo {
+            for_sale: kitty.for_sale,
+            kitty,
+            owner,
+            approved: Self::approved_account(kitty_id),
+            listing_expiry: Self::listing_expiry(kitty_id),
+        })
+    }
+
+    /// The sum of the prices of all kitties owned by `owner`, saturating rather than overflowing.
+    pub fn owned_value(owner: &T::AccountId) -> BalanceOf<T> {
+        Self::kitties_of(owner)
+            .into_iter()
+            .fold(Zero::zero(), |total: BalanceOf<T>, kitty_id| total.saturating_add(Self::kitty(kitty_id).price))
+    }
+
+    /// All kitties on `who`'s watchlist, in `WatchedKittiesArray` order.
+    pub fn watched_kitties(who: &T::AccountId) -> Vec<T::Hash> {
+        (0..Self::watched_kitty_count(who))
+            .map(|i| Self::watched_kitty_by_index((who.clone(), i)))
+            .collect()
+    }
+
+    /// All kitties of generation `gen`, in `GenerationKitties` order.
+    pub fn kitties_in_generation(gen: u64) -> Vec<T::Hash> {
+        (0..Self::generation_count(gen))
+            .map(|i| Self::kitty_of_generation_by_index((gen, i)))
+            .collect()
+    }
+
+    /// All kitties whose DNA starts with `prefix`, in `AllKittiesArray` order. Prefixes longer
+    /// than `MAX_DNA_PREFIX_LENGTH` match nothing.
+    ///
+    /// This reads every kitty, so it is meant for RPC and off-chain callers and must not be
+    /// used from a dispatchable.
+    pub fn find_by_dna_prefix(prefix: &[u8]) -> Vec<T::Hash> {
+        if prefix.len() > MAX_DNA_PREFIX_LENGTH {
+            return Vec::new();
+        }
+
+        (0..Self::all_kitties_count())
+            .map(Self::kitty_by_index)
+            .filter(|kitty_id| Self::kitty(*kitty_id).dna.as_ref().starts_with(prefix))
+            .collect()
+    }
+
+    // How a kitty looks is read straight from its DNA, one byte per trait:
+    //
+    //     byte 0: body colour
+    //     byte 1: eye type
+    //     byte 2: eye colour
+    //     byte 3: coat pattern
+    //
+    // The remaining bytes are free for future traits. Frontends should use these
+    // helpers rather than their own parsing, so the chain stays the source of truth.
+
+    /// The body colour encoded in `dna`.
+    pub fn body_color(dna: &T::Hash) -> u8 {
+        Self::dna_byte(dna, 0)
+    }
+
+    /// The eye type encoded in `dna`.
+    pub fn eye_type(dna: &T::Hash) -> u8 {
+        Self::dna_byte(dna, 1)
+    }
+
+    /// The eye colour encoded in `dna`.
+    pub fn eye_color(dna: &T::Hash) -> u8 {
+        Self::dna_byte(dna, 2)
+    }
+
+    /// The coat pattern encoded in `dna`.
+    pub fn pattern(dna: &T::Hash) -> u8 {
+        Self::dna_byte(dna, 3)
+    }
+
+    /// A single number to compare kitties by, or zero if `kitty_id` doesn't exist. Each trait
+    /// byte is split into sixteen tiers, from 0 for the commonest values up to 15 for those at
+    /// `RARE_TRAIT_FLOOR` and above, and the tiers are summed weighted by `TRAIT_RARITY_WEIGHTS`.
+    pub fn rarity_score(kitty_id: T::Hash) -> u32 {
+        if !<Kitties<T>>::exists(kitty_id) {
+            return 0;
+        }
+        let dna = Self::kitty(kitty_id).dna;
+
+        let traits = [Self::body_color(&dna), Self::eye_type(&dna), Self::eye_color(&dna), Self::pattern(&dna)];
+        traits.iter()
+            .zip(T::TRAIT_RARITY_WEIGHTS.iter())
+            .fold(0u32, |score, (byte, weight)| score.saturating_add(u32::from(*byte >> 4).saturating_mul(*weight)))
+    }
+
+    /// A stable seed for off-chain renderers to draw `kitty_id` from: the first 16 bytes of its
+    /// DNA, or all zeroes if it doesn't exist.
+    pub fn image_seed(kitty_id: T::Hash) -> [u8; 16] {
+        let mut seed = [0u8; 16];
+        if <Kitties<T>>::exists(kitty_id) {
+            let dna = Self::kitty(kitty_id).dna;
+            let len = dna.as_ref().len().min(seed.len());
+            seed[..len].copy_from_slice(&dna.as_ref()[..len]);
+        }
+        seed
+    }
+
+    /// Byte `i` of `dna`, or zero if it lies past the `DNA_BYTES` the runtime uses.
+    fn dna_byte(dna: &T::Hash, i: usize) -> u8 {
+        if i < T::DNA_BYTES {
+            dna.as_ref()[i]
+        } else {
+            0
+        }
+    }
+
+    /// `dna` with every byte past the first `DNA_BYTES` zeroed.
+    fn truncate_dna(mut dna: T::Hash) -> T::Hash {
+        for byte in dna.as_mut().iter_mut().skip(T::DNA_BYTES) {
+            *byte = 0;
+        }
+        dna
+    }
+
+    /// Checks that `kitty_id_1` and `kitty_id_2` are able to breed with each other right now.
+    fn ensure_can_breed(kitty_id_1: T::Hash, kitty_id_2: T::Hash) -> Result {
+        ensure!(kitty_id_1 != kitty_id_2, Error::BreedWithSelf.as_str());
+
+        // Check both kitty 1 and kitty 2 "exists"
+        ensure!(<Kitties<T>>::exists(kitty_id_1), Error::KittyNotExists.as_str());
+        ensure!(<Kitties<T>>::exists(kitty_id_2), Error::KittyNotExists.as_str());
+        ensure!(!Self::is_locked(kitty_id_1) && !Self::is_locked(kitty_id_2), Error::Locked.as_str());
+
+        // Both parents must have finished resting from their last breeding
+        let now = <system::Module<T>>::block_number();
+        ensure!(Self::kitty_cooldown(kitty_id_1) <= now, Error::Resting.as_str());
+        ensure!(Self::kitty_cooldown(kitty_id_2) <= now, Error::Resting.as_str());
+
+        ensure!(Self::breed_count(kitty_id_1) < T::MAX_BREEDS, Error::Infertile.as_str());
+        ensure!(Self::breed_count(kitty_id_2) < T::MAX_BREEDS, Error::Infertile.as_str());
+        ensure!(Self::energy(kitty_id_1) > 0 && Self::energy(kitty_id_2) > 0, Error::TooTired.as_str());
+
+        let child_gen = rstd::cmp::max(Self::kitty(kitty_id_1).gen, Self::kitty(kitty_id_2).gen)
+            .checked_add(1)
+            .ok_or(Error::Overflow)?;
+        ensure!(child_gen <= T::MAX_GENERATION, Error::MaxGenerationReached.as_str());
+
+        Ok(())
+    }
+
+    /// How much energy `kitty_id` has now. A kitty that has never bred is fully rested, and one
+    /// that has gets an energy back for every `ENERGY_REGEN_BLOCKS` since it last did, up to
+    /// `MAX_ENERGY`.
+    pub fn energy(kitty_id: T::Hash) -> u32 {
+        let last_bred = match Self::last_energy_block(kitty_id) {
+            Some(block) => block,
+            None => return T::MAX_ENERGY,
+        };
+
+        let elapsed = <system::Module<T>>::block_number().saturating_sub(last_bred).as_();
+        let regained = match T::ENERGY_REGEN_BLOCKS {
+            0 => T::MAX_ENERGY,
+            blocks => (elapsed / blocks).min(u64::from(u32::max_value())) as u32,
+        };
+        Self::stored_energy(kitty_id).saturating_add(regained).min(T::MAX_ENERGY)
+    }
+
+    /// Uses up one of `kitty_id`'s energy. Regaining the next one starts over from `now`.
+    fn spend_energy(kitty_id: T::Hash, now: T::BlockNumber) {
+        let energy = Self::energy(kitty_id).saturating_sub(1);
+        <Energy<T>>::insert(kitty_id, energy);
+        <LastEnergyBlock<T>>::insert(kitty_id, now);
+    }
+
+    /// Breeds `kitty_id_1` with `kitty_id_2` and mints the child to `owner`, who also pays
+    /// `sire_fee` to the sire's owner when it is given. No fee is paid unless the child can be
+    /// minted.
+    fn do_breed(
+        owner: T::AccountId,
+        kitty_id_1: T::Hash,
+        kitty_id_2: T::Hash,
+        sire_fee: Option<(T::AccountId, BalanceOf<T>)>,
+    ) -> Result {
+        Self::ensure_can_breed(kitty_id_1, kitty_id_2)?;
+        Self::ensure_can_mint(&owner, 1)?;
+
+        let kitty_1 = Self::kitty(kitty_id_1);
+        let kitty_2 = Self::kitty(kitty_id_2);
+        let breed_fee = Self::breed_fee(rstd::cmp::max(kitty_1.gen, kitty_2.gen))?;
+
+        // Generate a `random_hash` that also depends on both parents
+        let nonce = Self::peek_nonce()?;
+        let random_hash = (Self::generate_random_hash(&owner, nonce), kitty_id_1, kitty_id_2)
+            .using_encoded(<T as system::Trait>::Hashing::hash);
+
+        let final_dna = Self::preview_dna(kitty_id_1, kitty_id_2, random_hash);
+        Self::ensure_new_kitty(random_hash, final_dna, &[])?;
+
+        // Nothing is rolled back if a payment fails, so check they can all be made before making any
+        let fees: Vec<_> = sire_fee.into_iter().chain(breed_fee.clone()).collect();
+        let total_fees = Self::ensure_can_pay(&owner, &fees)?;
+        ensure!(
+            <T::Currency as Currency<_>>::free_balance(&owner) >= total_fees,
+            Error::CannotAffordFees.as_str()
+        );
+
+        for (to, amount) in fees {
+            Self::pay(&owner, &to, amount)?;
+        }
+        if let Some((treasury, fee)) = breed_fee {
+            Self::note_fee_collected(&owner, treasury, fee);
+        }
+        Self::next_nonce()?;
+
+        // Create a `new_kitty` using:
+        //      - `random_hash` as `id`
+        //      - `final_dna` as `dna`
+        //      - 0 as `price`
+        //      - the max of the parent's `gen` + 1
+        //          - Hint: `rstd::cmp::max(1, 5) + 1` is `6`
+        let new_kitty = Kitty {
+            id: random_hash,
+            dna: final_dna,
+            price: <BalanceOf<T> as As<u64>>::sa(0),
+            gen: rstd::cmp::max(kitty_1.gen, kitty_2.gen) + 1,
+            name: Vec::new(),
+            birth_block: Zero::zero(),
+            for_sale: false,
+        };
+
+        // `mint()` your new kitty
+        Self::mint(owner.clone(), random_hash, new_kitty)?;
+
+        let now = <system::Module<T>>::block_number();
+        <KittyParents<T>>::insert(random_hash, (kitty_id_1, kitty_id_2));
+        <KittyCooldown<T>>::insert(kitty_id_1, now + Self::cooldown_blocks(kitty_1.gen));
+        <KittyCooldown<T>>::insert(kitty_id_2, now + Self::cooldown_blocks(kitty_2.gen));
+        <BreedCount<T>>::mutate(kitty_id_1, |count| *count += 1);
+        <BreedCount<T>>::mutate(kitty_id_2, |count| *count += 1);
+        Self::spend_energy(kitty_id_1, now);
+        Self::spend_energy(kitty_id_2, now);
+
+        let breed_event_count = Self::breed_event_count();
+        <BreedEvents<T>>::insert(breed_event_count, (kitty_id_1, kitty_id_2, random_hash, now));
+        <BreedEventCount<T>>::put(breed_event_count.saturating_add(1));
+
+        Self::deposit_event(RawEvent::Bred(owner, random_hash, kitty_id_1, kitty_id_2));
+
+        Ok(())
+    }
+
+    /// The DNA of a generation 0 kitty made from `seed`. It is `seed` itself, except that each
+    /// of the four trait bytes is pushed into the rare range, `RARE_TRAIT_FLOOR` and up, for
+    /// the percentage of kitties `RARE_TRAIT_PERCENT` gives for that trait.
+    pub fn roll_gen0_dna(seed: T::Hash) -> T::Hash {
+        let mut dna = seed;
+        let rolls = (seed, &b"rarity"[..]).using_encoded(<T as system::Trait>::Hashing::hash);
+
+        for (i, percent) in T::RARE_TRAIT_PERCENT.iter().enumerate() {
+            if u32::from(rolls.as_ref()[i]) % 100 < *percent {
+                dna.as_mut()[i] |= RARE_TRAIT_FLOOR;
+            }
+        }
+
+        dna
+    }
+
+    /// The DNA a child of `kitty_id_1` and `kitty_id_2` gets when bred with `seed`, without
+    /// breeding them. A bred kitty's id is the seed it was bred with, so this is also how to
+    /// check a child's DNA after the fact.
+    pub fn preview_dna(kitty_id_1: T::Hash, kitty_id_2: T::Hash, seed: T::Hash) -> T::Hash {
+        let dna_1 = Self::kitty(kitty_id_1).dna;
+        let dna_2 = Self::kitty(kitty_id_2).dna;
+
+        // Our gene splicing algorithm is set by the runtime, feel free to make it your own
+        let dna = T::GeneAlgorithm::combine(&dna_1, &dna_2, &seed);
+        Self::truncate_dna(Self::mutate(dna, seed))
+    }
+
+    /// `dna` with each byte swapped for a random one `MUTATION_RATE_PERCENT` percent of the time.
+    /// Which bytes mutate, and into what, only depends on `seed`.
+    fn mutate(mut dna: T::Hash, seed: T::Hash) -> T::Hash {
+        if T::MUTATION_RATE_PERCENT == 0 {
+            return dna;
+        }
+
+        let rolls = (seed, &b"mutation"[..]).using_encoded(<T as system::Trait>::Hashing::hash);
+        let genes = (seed, &b"genes"[..]).using_encoded(<T as system::Trait>::Hashing::hash);
+
+        for (i, (roll, gene)) in rolls.as_ref().iter().zip(genes.as_ref().iter()).enumerate() {
+            if u32::from(*roll) % 100 < T::MUTATION_RATE_PERCENT {
+                dna.as_mut()[i] = *gene;
+            }
+        }
+
+        dna
+    }
+
+    /// The next `count` generation 0 kitties `owner` would get, checked to all be mintable
+    /// without using up any nonces, so fees are only charged for kitties that can be made.
+    fn prepare_gen0_kitties(
+        owner: &T::AccountId,
+        count: u32,
+    ) -> rstd::result::Result<Vec<(T::Hash, Kitty<T::Hash, BalanceOf<T>, T::BlockNumber>)>, &'static str> {
+        Self::ensure_can_mint(owner, u64::from(count))?;
+        Self::ensure_can_mint_gen0(u64::from(count))?;
+
+        let first_nonce = Self::peek_nonce()?;
+        let mut kitties = Vec::new();
+        for i in 0..u64::from(count) {
+            // Every nonce used, and the one after the last, has to fit
+            let nonce = first_nonce.checked_add(i)
+                .and_then(|nonce| nonce.checked_add(1).map(|_| nonce))
+                .ok_or(Error::NonceOverflow)?;
+            let random_hash = Self::generate_random_hash(owner, nonce);
+
+            let new_kitty = Kitty {
+                id: random_hash,
+                dna: Self::truncate_dna(Self::roll_gen0_dna(random_hash)),
+                price: <BalanceOf<T> as As<u64>>::sa(0),
+                gen: 0,
+                name: Vec::new(),
+                birth_block: Zero::zero(),
+                for_sale: false,
+            };
+
+            Self::ensure_new_kitty(random_hash, new_kitty.dna, &kitties)?;
+            kitties.push((random_hash, new_kitty));
+        }
+
+        Ok(kitties)
+    }
+
+    /// Mints kitties from `prepare_gen0_kitties` to `owner`, using up a nonce for each.
+    fn mint_gen0_kitties(
+        owner: &T::AccountId,
+        kitties: Vec<(T::Hash, Kitty<T::Hash, BalanceOf<T>, T::BlockNumber>)>,
+    ) -> Result {
+        for (kitty_id, new_kitty) in kitties {
+            Self::next_nonce()?;
+            Self::mint(owner.clone(), kitty_id, new_kitty)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks a kitty `kitty_id` with `dna` could be minted alongside the existing kitties and
+    /// the `pending` ones about to be minted with it: its id must be new, and so must its DNA
+    /// if the runtime has `REQUIRE_UNIQUE_DNA`.
+    fn ensure_new_kitty(
+        kitty_id: T::Hash,
+        dna: T::Hash,
+        pending: &[(T::Hash, Kitty<T::Hash, BalanceOf<T>, T::BlockNumber>)],
+    ) -> Result {
+        ensure!(
+            !<KittyOwner<T>>::exists(kitty_id) && !pending.iter().any(|(id, _)| *id == kitty_id),
+            Error::KittyExists.as_str()
+        );
+
+        if T::REQUIRE_UNIQUE_DNA {
+            let dna = Self::truncate_dna(dna);
+            ensure!(
+                !Self::dna_exists(dna) && !pending.iter().any(|(_, kitty)| kitty.dna == dna),
+                Error::DnaExists.as_str()
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The ancestors of `kitty_id` up to `depth` generations back, nearest first.
+    ///
+    /// Each ancestor is listed once even if it shows up on several branches, and the walk
+    /// stops early at generation 0 kitties or at burned ones.
+    pub fn ancestors(kitty_id: T::Hash, depth: u32) -> Vec<T::Hash> {
+        let mut ancestors = Vec::new();
+        let mut generation = vec![kitty_id];
+
+        for _ in 0..depth {
+            let mut parents = Vec::new();
+            for id in generation {
+                if !<KittyParents<T>>::exists(id) {
+                    continue;
+                }
+                let (parent_1, parent_2) = Self::parents_of(id);
+                for parent in &[parent_1, parent_2] {
+                    if *parent != id && !ancestors.contains(parent) {
+                        ancestors.push(*parent);
+                        parents.push(*parent);
+                    }
+                }
+            }
+
+            if parents.is_empty() {
+                break;
+            }
+            generation = parents;
+        }
+
+        ancestors
+    }
+
+    /// A fresh hash for `sender` to use as a kitty id and DNA.
+    ///
+    /// `random_seed` alone is the same for every extrinsic in a block and known ahead of
+    /// it, so it is mixed with a `nonce` from `next_nonce()`, the block number and extrinsic index.
+    fn generate_random_hash(sender: &T::AccountId, nonce: u64) -> T::Hash {
+        (
+            <system::Module<T>>::random_seed(),
+            sender,
+            nonce,
+            <system::Module<T>>::block_number(),
+            <system::Module<T>>::extrinsic_index(),
+        ).using_encoded(<T as system::Trait>::Hashing::hash)
+    }
+
+    /// The current nonce, which is bumped so that no two calls get the same one.
+    fn next_nonce() -> rstd::result::Result<u64, &'static str> {
+        let nonce = Self::peek_nonce()?;
+        <Nonce<T>>::put(nonce + 1);
+
+        Ok(nonce)
+    }
+
+    /// The nonce `next_nonce` hands out next, without using it up. Fails if `next_nonce` would.
+    fn peek_nonce() -> rstd::result::Result<u64, &'static str> {
+        let nonce = <Nonce<T>>::get();
+        nonce.checked_add(1).ok_or(Error::NonceOverflow)?;
+
+        Ok(nonce)
+    }
+
+    /// Checks that `count` more kitties can be minted to `to` without any of the
+    /// counters in `mint()` overflowing.
+    fn ensure_can_mint(to: &T::AccountId, count: u64) -> Result {
+        let new_owned_kitty_count = Self::owned_kitty_count(to).checked_add(count)
+            .ok_or(Error::Overflow)?;
+        ensure!(new_owned_kitty_count <= T::MAX_KITTIES_PER_ACCOUNT, Error::OwnKittyLimit.as_str());
+        let new_all_kitties_count = Self::all_kitties_count().checked_add(count)
+            .ok_or(Error::Overflow)?;
+        ensure!(new_all_kitties_count <= T::MAX_KITTIES, Error::MaxSupply.as_str());
+
+        Ok(())
+    }
+
+    /// Checks that `count` more generation 0 kitties fit under `MAX_GEN0`.
+    fn ensure_can_mint_gen0(count: u64) -> Result {
+        let new_gen0_count = Self::gen0_count().checked_add(count).ok_or(Error::Overflow)?;
+        ensure!(new_gen0_count <= T::MAX_GEN0, Error::Gen0Limit.as_str());
+
+        Ok(())
+    }
+
+    fn mint(to: T::AccountId, kitty_id: T::Hash, new_kitty: Kitty<T::Hash, BalanceOf<T>, T::BlockNumber>) -> Result {
+        let dna = Self::truncate_dna(new_kitty.dna);
+        let gen = new_kitty.gen;
+        Self::do_mint(&to, kitty_id, new_kitty)?;
+
+        <LastCreated<T>>::insert(&to, kitty_id);
+        let owned_kitty_count = Self::owned_kitty_count(&to);
+        Self::deposit_event(RawEvent::Minted(to.clone(), kitty_id, gen));
+        Self::deposit_event(RawEvent::Created(to, kitty_id, dna, owned_kitty_count));
+
+        Ok(())
+    }
+
+    /// Does all the storage work of `mint()` without depositing an event, so it can
+    /// also be used while building the genesis state.
+    fn do_mint(to: &T::AccountId, kitty_id: T::Hash, mut new_kitty: Kitty<T::Hash, BalanceOf<T>, T::BlockNumber>) -> Result {
+        ensure!(!<KittyOwner<T>>::exists(kitty_id), Error::KittyExists.as_str());
+
+        let owned_kitty_count = Self::owned_kitty_count(to);
+
+        let new_owned_kitty_count = owned_kitty_count.checked_add(1)
+            .ok_or(Error::Overflow)?;
+        ensure!(new_owned_kitty_count <= T::MAX_KITTIES_PER_ACCOUNT, Error::OwnKittyLimit.as_str());
+
+        let all_kitties_count = Self::all_kitties_count();
+
+        let new_all_kitties_count = all_kitties_count.checked_add(1)
+            .ok_or(Error::Overflow)?;
+        ensure!(new_all_kitties_count <= T::MAX_KITTIES, Error::MaxSupply.as_str());
+
+        let gen = new_kitty.gen;
+        if gen == 0 {
+            Self::ensure_can_mint_gen0(1)?;
+        }
+        // Whatever produced the DNA, only the bytes the runtime uses may be set
+        new_kitty.dna = Self::truncate_dna(new_kitty.dna);
+        let dna = new_kitty.dna;
+        if T::REQUIRE_UNIQUE_DNA {
+            ensure!(!Self::dna_exists(dna), Error::DnaExists.as_str());
+        }
+
+        new_kitty.birth_block = <system::Module<T>>::block_number();
+
+        <Kitties<T>>::insert(kitty_id, new_kitty);
+        <KittyOwner<T>>::insert(kitty_id, to);
+        <KittyBreeder<T>>::insert(kitty_id, to);
+        <KittyParents<T>>::insert(kitty_id, (kitty_id, kitty_id));
+
+        <AllKittiesArray<T>>::insert(all_kitties_count, kitty_id);
+        <AllKittiesCount<T>>::put(new_all_kitties_count);
+        <AllKittiesIndex<T>>::insert(kitty_id, all_kitties_count);
+
+        <OwnedKittiesArray<T>>::insert((to.clone(), owned_kitty_count), kitty_id);
+        <OwnedKittiesCount<T>>::insert(to, new_owned_kitty_count);
+        <OwnedKittiesIndex<T>>::insert(kitty_id, owned_kitty_count);
+
+        let generation_count = Self::generation_count(gen);
+        <GenerationKitties<T>>::insert((gen, generation_count), kitty_id);
+        <GenerationCount<T>>::insert(gen, generation_count.saturating_add(1));
+        <GenerationIndex<T>>::insert(kitty_id, generation_count);
+        if gen == 0 {
+            <Gen0Count<T>>::mutate(|count| *count += 1);
+        }
+        if T::REQUIRE_UNIQUE_DNA {
+            <DnaExists<T>>::insert(dna, true);
+        }
+        Self::record_owner(kitty_id, to);
+
+        Ok(())
+    }
+
+    /// Adds `owner` to the end of the kitty's `TransferHistory`, dropping the oldest entries
+    /// once there are more than `MAX_HISTORY`.
+    fn record_owner(kitty_id: T::Hash, owner: &T::AccountId) {
+        let now = <system::Module<T>>::block_number();
+        <TransferHistory<T>>::mutate(kitty_id, |history| {
+            history.push((owner.clone(), now));
+            let max = T::MAX_HISTORY as usize;
+            if history.len() > max {
+                let excess = history.len() - max;
+                history.drain(..excess);
+            }
+        });
+    }
+
+    /// Checks that `who` may list `kitty_id` for `new_price`.
+    fn ensure_can_set_price(who: &T::AccountId, kitty_id: T::Hash, new_price: BalanceOf<T>) -> Result {
+        ensure!(<Kitties<T>>::exists(kitty_id), Error::KittyNotExists.as_str());
+
+        let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+        ensure!(owner == *who, Error::NotOwner.as_str());
+        // A kitty can only be on one kind of sale at a time
+        ensure!(!Self::is_auctioned(kitty_id), Error::InAuction.as_str());
+        ensure!(!Self::is_locked(kitty_id), Error::Locked.as_str());
+        ensure!(!Self::is_leased(kitty_id), Error::Leased.as_str());
+
+        ensure!(new_price >= <BalanceOf<T> as As<u64>>::sa(T::MIN_LISTING_PRICE), Error::PriceBelowMinimum.as_str());
+        ensure!(new_price <= <BalanceOf<T> as As<u64>>::sa(T::MAX_LISTING_PRICE), Error::PriceAboveMaximum.as_str());
+
+        Ok(())
+    }
+
+    /// Lists a kitty already checked with `ensure_can_set_price`. Fails without listing it if
+    /// the listing would expire past the last block.
+    fn list_for_sale(seller: T::AccountId, kitty_id: T::Hash, new_price: BalanceOf<T>, expires_in: T::BlockNumber) -> Result {
+        let expiry = <system::Module<T>>::block_number().checked_add(&expires_in)
+            .ok_or(Error::Overflow)?;
+
+        let mut kitty = Self::kitty(kitty_id);
+        // Relisting at a new price doesn't add another listing
+        if !kitty.for_sale {
+            <ForSaleCount<T>>::mutate(|count| *count = count.saturating_add(1));
+        }
+        kitty.price = new_price;
+        kitty.for_sale = true;
+
+        <Kitties<T>>::insert(kitty_id, kitty);
+
+        if expires_in.is_zero() {
+            <ListingExpiry<T>>::remove(kitty_id);
+        } else {
+            <ListingExpiry<T>>::insert(kitty_id, expiry);
+            <ListingExpiryQueue<T>>::mutate(expiry, |queue| queue.push(kitty_id));
+        }
+
+        Self::deposit_event(RawEvent::PriceSet(seller, kitty_id, new_price));
+
+        Ok(())
+    }
+
+    /// Moves a kitty that has just been paid for from `seller` to `buyer`. Every sale runs
+    /// `ensure_can_transfer_from(seller, buyer, kitty_id)` before taking the payment.
+    fn hand_over_sold_kitty(seller: T::AccountId, buyer: T::AccountId, kitty_id: T::Hash) {
+        Self::transfer_from(seller, buyer, kitty_id)
+            .expect("`ensure_can_transfer_from` has checked every way `transfer_from` can fail, \
+            and paying for the kitty does not change any of them; \
+            qed");
+    }
+
+    /// Checks everything that could make `transfer_from(from, to, kitty_id)` fail, without
+    /// touching storage. Callers that need to do other work first, like taking payment,
+    /// should run this before that work.
+    fn ensure_can_transfer_from(from: &T::AccountId, to: &T::AccountId, kitty_id: T::Hash) -> Result {
+        let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+        ensure!(owner == *from, Error::NotOwner.as_str());
+        ensure!(!Self::is_auctioned(kitty_id), Error::InAuction.as_str());
+        ensure!(!Self::is_locked(kitty_id), Error::Locked.as_str());
+        ensure!(!Self::is_leased(kitty_id), Error::Leased.as_str());
+
+        Self::ensure_can_hand_over(from, to, kitty_id)
+    }
+
+    /// The checks in `ensure_can_transfer_from` that the owner has no say over: who may
+    /// receive the kitty, how recently it last moved and how many kitties each side can hold.
+    /// These still apply to `force_transfer`.
+    fn ensure_can_hand_over(from: &T::AccountId, to: &T::AccountId, kitty_id: T::Hash) -> Result {
+        let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+        ensure!(owner == *from, Error::NotOwner.as_str());
+        // The module's account holds escrowed kitties for everyone at once, so none of the limits
+        // on ordinary recipients apply to it, and escrow doesn't restart the transfer cooldown
+        if *to == Self::module_account_id() {
+            Self::owned_kitty_count(from).checked_sub(1)
+                .ok_or(Error::Overflow)?;
+            return Ok(());
+        }
+        ensure!(T::AccountValidator::is_valid_recipient(to), Error::InvalidRecipient.as_str());
+        ensure!(T::TransferGate::can_receive(to), Error::RecipientNotPermitted.as_str());
+        if let Some(last_transfer) = Self::last_transfer_block(kitty_id) {
+            let cooldown = <T::BlockNumber as As<u64>>::sa(T::TRANSFER_COOLDOWN);
+            let now = <system::Module<T>>::block_number();
+            ensure!(now >= last_transfer.saturating_add(cooldown), Error::TransferredTooRecently.as_str());
+        }
+
+        let new_owned_kitty_count_to = Self::owned_kitty_count(to).checked_add(1)
+            .ok_or(Error::Overflow)?;
+        ensure!(new_owned_kitty_count_to <= T::MAX_KITTIES_PER_ACCOUNT, Error::RecipientKittyLimit.as_str());
+        Self::owned_kitty_count(from).checked_sub(1)
+            .ok_or(Error::Overflow)?;
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Moves `kitty_id` from `from` into the module's account, which holds it until it is
+    /// handed on. `ensure_can_hand_over` lets the module's account take any number of kitties.
+    fn escrow(from: T::AccountId, kitty_id: T::Hash) -> Result {
+        Self::transfer_from(from, Self::module_account_id(), kitty_id)
+    }
+
+    fn transfer_from(from: T::AccountId, to: T::AccountId, kitty_id: T::Hash) -> Result {
+        Self::ensure_can_transfer_from(&from, &to, kitty_id)?;
+
+        let owned_kitty_count_from = Self::owned_kitty_count(&from);
+        let owned_kitty_count_to = Self::owned_kitty_count(&to);
+
+        // Used `checked_add()` to increment the `owned_kitty_count_to` by one into `new_owned_kitty_count_to`
+        let new_owned_kitty_count_to = owned_kitty_count_to.checked_add(1).ok_or(Error::Overflow)?;
+        // Used `checked_sub()` to increment the `owned_kitty_count_from` by one into `new_owned_kitty_count_from`
+        //      - Return an `Err()` if overflow or underflow
+        let new_owned_kitty_count_from = owned_kitty_count_from.checked_sub(1).ok_or(Error::Overflow)?;
+
+        // "Swap and pop"
+        // We our convenience storage items to help simplify removing an element from the OwnedKittiesArray
+        // We switch the last element of OwnedKittiesArray with the element we want to remove
+        let kitty_index = <OwnedKittiesIndex<T>>::get(kitty_id);
+        if kitty_index != new_owned_kitty_count_from {
+            let last_kitty_id = <OwnedKittiesArray<T>>::get((from.clone(), new_owned_kitty_count_from));
+            <OwnedKittiesArray<T>>::insert((from.clone(), kitty_index), last_kitty_id);
+            <OwnedKittiesIndex<T>>::insert(last_kitty_id, kitty_index);
+        }
+        
+        // Update KittyOwner for `kitty_id`
+        <KittyOwner<T>>::insert(kitty_id, &to);
+        // Update OwnedKittiesIndex for `kitty_id`
+        <OwnedKittiesIndex<T>>::insert(kitty_id, owned_kitty_count_to);
+
+        // Update OwnedKittiesArray to remove the element from `from`, and add an element to `to`
+        //      - HINT: The last element in OwnedKittiesArray(from) is `new_owned_kitty_count_from`
+        //              The last element in OwnedKittiesArray(to) is `owned_kitty_count_to`
+        <OwnedKittiesArray<T>>::insert((to.clone(), owned_kitty_count_to), kitty_id);
+        <OwnedKittiesArray<T>>::remove((from.clone(), new_owned_kitty_count_from));
+
+        // Update the OwnedKittiesCount for `from` and `to`
+        <OwnedKittiesCount<T>>::insert(&from, new_owned_kitty_count_from);
+        <OwnedKittiesCount<T>>::insert(&to, new_owned_kitty_count_to);
+
+        // An approval granted by the previous owner must not outlive the transfer
+        if <KittyApprovals<T>>::take(kitty_id).is_some() {
+            Self::deposit_event(RawEvent::ApprovalCleared(kitty_id));
+        }
+        // Neither should the sire fee they asked for, or any listing they made
+        <SireListings<T>>::remove(kitty_id);
+        let mut kitty = Self::kitty(kitty_id);
+        if kitty.for_sale {
+            Self::note_delisted();
+        }
+        if kitty.for_sale || !kitty.price.is_zero() {
+            kitty.price = <BalanceOf<T> as As<u64>>::sa(0);
+            kitty.for_sale = false;
+            <Kitties<T>>::insert(kitty_id, kitty);
+        }
+        <ListingExpiry<T>>::remove(kitty_id);
+        // Any lease is over by now, and the new owner hasn't offered one
+        <Leases<T>>::remove(kitty_id);
+        <LeaseOffers<T>>::remove(kitty_id);
+        Self::move_shares(kitty_id, &from, &to);
+        if to != Self::module_account_id() {
+            <LastTransferBlock<T>>::insert(kitty_id, <system::Module<T>>::block_number());
+        }
+        // Nobody needs to offer on a kitty they own, so hand the new owner's offer back
+        let offer = <Offers<T>>::take((kitty_id, to.clone()));
+        if !offer.is_zero() {
+            <T::Currency as ReservableCurrency<_>>::unreserve(&to, offer);
+            Self::forget_offer(kitty_id, &to);
+            Self::deposit_event(RawEvent::OfferWithdrawn(to.clone(), kitty_id));
+        }
+        Self::record_owner(kitty_id, &to);
+
+        T::OnKittyTransfer::on_transfer(&from, &to, kitty_id);
+
+        Self::deposit_event(RawEvent::Transferred(from, to, kitty_id));
+
+        Ok(())
+    }
+
+    /// Whether `who` may move `kitty_id` on behalf of its `owner`, either by owning it,
+    /// by holding the approval for this kitty or by being one of the owner's operators.
+    fn can_transfer(who: &T::AccountId, owner: &T::AccountId, kitty_id: T::Hash) -> bool {
+        who == owner
+            || Self::approved_account(kitty_id).as_ref() == Some(who)
+            || Self::is_approved_for_all((owner.clone(), who.clone()))
+    }
+
+    /// `percent` percent of `amount`, with the percentage capped at 100.
+    fn percent_of(amount: BalanceOf<T>, percent: u32) -> rstd::result::Result<BalanceOf<T>, &'static str> {
+        let percent = <BalanceOf<T> as As<u64>>::sa(u64::from(rstd::cmp::min(percent, 100)));
+        let scaled = amount.checked_mul(&percent).ok_or(Error::Overflow)?;
+
+        Ok(scaled / <BalanceOf<T> as As<u64>>::sa(100))
+    }
+
+    /// Brings `BestOffer` up to date after `who`'s offer on `kitty_id` changed. A new or raised
+    /// offer only has to be compared with the current best, but if the best offer itself went
+    /// down or away, every other open offer on the kitty has to be looked at again.
+    fn update_best_offer(kitty_id: T::Hash, who: &T::AccountId) {
+        let amount = Self::offer((kitty_id, who.clone()));
+
+        match Self::best_offer(kitty_id) {
+            Some((ref best, _)) if best == who => Self::recompute_best_offer(kitty_id),
+            Some((_, best_amount)) if amount <= best_amount => (),
+            _ if amount.is_zero() => (),
+            _ => <BestOffer<T>>::insert(kitty_id, (who.clone(), amount)),
+        }
+    }
+
+    /// Hands every open offer on `kitty_id` back to whoever made it.
+    fn refund_offers(kitty_id: T::Hash) {
+        for offerer in <Offerers<T>>::take(kitty_id) {
+            let amount = <Offers<T>>::take((kitty_id, offerer.clone()));
+            <T::Currency as ReservableCurrency<_>>::unreserve(&offerer, amount);
+            Self::deposit_event(RawEvent::OfferWithdrawn(offerer, kitty_id));
+        }
+        <BestOffer<T>>::remove(kitty_id);
+    }
+
+    /// Drops a swap proposal that has just been removed from both of its kitties' `KittySwaps`.
+    fn forget_swap(proposal_id: u64, proposal: &SwapProposal<T::AccountId, T::Hash>) {
+        for kitty_id in &[proposal.my_kitty, proposal.their_kitty] {
+            <KittySwaps<T>>::mutate(kitty_id, |swaps| swaps.retain(|id| *id != proposal_id));
+        }
+    }
+
+    /// Drops `who` from the kitty's offerers once their offer has been removed.
+    fn forget_offer(kitty_id: T::Hash, who: &T::AccountId) {
+        <Offerers<T>>::mutate(kitty_id, |offerers| offerers.retain(|offerer| offerer != who));
+        Self::update_best_offer(kitty_id, who);
+    }
+
+    /// Finds the best offer on `kitty_id` by going through all of its offerers, so this costs
+    /// one read per open offer. Ties go to whoever offered first.
+    fn recompute_best_offer(kitty_id: T::Hash) {
+        let mut best: Option<(T::AccountId, BalanceOf<T>)> = None;
+        for offerer in Self::offerers_of(kitty_id) {
+            let amount = Self::offer((kitty_id, offerer.clone()));
+            let is_better = match best {
+                Some((_, best_amount)) => amount > best_amount,
+                None => true,
+            };
+            if is_better {
+                best = Some((offerer, amount));
+            }
+        }
+
+        match best {
+            Some(best) => <BestOffer<T>>::insert(kitty_id, best),
+            None => <BestOffer<T>>::remove(kitty_id),
+        }
+    }
+
+    /// Pays `price` from `buyer` for `kitty_id`, splitting it between the marketplace's
+    /// fee collector, the kitty's breeder and the `seller`.
+    fn pay_for_kitty(buyer: &T::AccountId, seller: &T::AccountId, kitty_id: T::Hash, price: BalanceOf<T>) -> Result {
+        let sale = Self::sale_payments(buyer, seller, kitty_id, price)?;
+        ensure!(
+            <T::Currency as Currency<_>>::free_balance(buyer) >= sale.total,
+            Error::InsufficientBalance.as_str()
+        );
+
+        Self::make_sale_payments(buyer, kitty_id, price, sale)
+    }
+
+    /// Works out how `price` for `kitty_id` is split up and checks `buyer` could make every
+    /// one of the payments, apart from having the balance for them, without making any.
+    fn sale_payments(
+        buyer: &T::AccountId,
+        seller: &T::AccountId,
+        kitty_id: T::Hash,
+        price: BalanceOf<T>,
+    ) -> rstd::result::Result<SalePayments<T::AccountId, BalanceOf<T>>, &'static str> {
+        // The marketplace only takes a cut once root has set a collector
+        let fee = match Self::fee_collector() {
+            Some(collector) => Some((collector, Self::percent_of(price, T::MARKETPLACE_FEE_PERCENT)?)),
+            None => None,
+        };
+        // Selling a kitty you bred yourself earns no royalty on top of the price, and a breeder
+        // account that can't receive the royalty forfeits it to the seller
+        let royalty = match Self::breeder_of(kitty_id) {
+            Some(ref breeder) if breeder != seller => Some((breeder.clone(), Self::percent_of(price, T::ROYALTY_PERCENT)?)),
+            _ => None,
+        }.filter(|royalty| Self::ensure_can_pay(buyer, &[royalty.clone()]).is_ok());
+
+        let mut seller_amount = price;
+        for (_, amount) in fee.iter().chain(royalty.iter()) {
+            seller_amount = seller_amount.checked_sub(amount).ok_or(Error::FeesExceedPrice)?;
+        }
+
+        // Co-owners get their share of what the seller makes, and the seller keeps whatever is left
+        let total_shares = <BalanceOf<T> as As<u64>>::sa(u64::from(TOTAL_SHARES));
+        let mut payouts = Vec::new();
+        for holder in Self::shareholders(kitty_id).into_iter().filter(|holder| holder != seller) {
+            let shares = <BalanceOf<T> as As<u64>>::sa(u64::from(Self::kitty_shares((kitty_id, holder.clone()))));
+            let amount = seller_amount.checked_mul(&shares).ok_or(Error::Overflow)? / total_shares;
+            payouts.push((holder, amount));
+        }
+        for (_, amount) in payouts.iter() {
+            seller_amount = seller_amount.checked_sub(amount).ok_or(Error::Overflow)?;
+        }
+
+        // The payment is split over several transfers and nothing is rolled back if one fails,
+        // so make sure the buyer can make all of them before making any
+        let payments: Vec<_> = fee.iter().cloned()
+            .chain(royalty.iter().cloned())
+            .chain(payouts)
+            .chain(Some((seller.clone(), seller_amount)))
+            .collect();
+        let total = Self::ensure_can_pay(buyer, &payments)?;
+
+        Ok(SalePayments { payments, total, fee, royalty })
+    }
+
+    /// Makes the payments `sale_payments` worked out for a sale of `kitty_id` at `price`.
+    fn make_sale_payments(
+        buyer: &T::AccountId,
+        kitty_id: T::Hash,
+        price: BalanceOf<T>,
+        sale: SalePayments<T::AccountId, BalanceOf<T>>,
+    ) -> Result {
+        let SalePayments { payments, fee, royalty, .. } = sale;
+        for (to, amount) in payments {
+            Self::pay(buyer, &to, amount)?;
+        }
+        if let Some((collector, amount)) = fee {
+            Self::deposit_event(RawEvent::FeeCollected(collector, amount));
+        }
+        if let Some((breeder, amount)) = royalty {
+            Self::deposit_event(RawEvent::RoyaltyPaid(breeder, amount));
+        }
+        // The buyer gets the whole kitty
+        Self::clear_shares(kitty_id);
+        <TotalVolume<T>>::mutate(|volume| *volume = volume.saturating_add(price));
+
+        Ok(())
+    }
+
+    /// Hands `from`'s shares in `kitty_id` to `to`, who is taking over as its owner. The other
+    /// shareholders keep theirs.
+    fn move_shares(kitty_id: T::Hash, from: &T::AccountId, to: &T::AccountId) {
+        let mut holders = Self::shareholders(kitty_id);
+        if holders.is_empty() {
+            return;
+        }
+
+        let shares = <KittyShares<T>>::take((kitty_id, from.clone()));
+        holders.retain(|holder| holder != from);
+        if !holders.contains(to) {
+            holders.push(to.clone());
+        }
+
+        if holders.len() == 1 {
+            Self::clear_shares(kitty_id);
+        } else {
+            <KittyShares<T>>::mutate((kitty_id, to.clone()), |held| *held += shares);
+            <Shareholders<T>>::insert(kitty_id, holders);
+        }
+    }
+
+    /// Forgets every share in `kitty_id`, leaving its owner with all of them.
+    fn clear_shares(kitty_id: T::Hash) {
+        for holder in <Shareholders<T>>::take(kitty_id) {
+            <KittyShares<T>>::remove((kitty_id, holder));
+        }
+    }
+
+    /// Takes one listing off `ForSaleCount`, for a kitty that was just taken off sale.
+    fn note_delisted() {
+        <ForSaleCount<T>>::mutate(|count| *count = count.saturating_sub(1));
+    }
+
+    /// Takes down up to `MAX_EXPIRIES_PER_BLOCK` listings that have lapsed by block `now`,
+    /// leaving the rest queued for the block after.
+    fn expire_listings(now: T::BlockNumber) {
+        let mut queue = <ListingExpiryQueue<T>>::take(now);
+        let budget = rstd::cmp::min(queue.len(), T::MAX_EXPIRIES_PER_BLOCK as usize);
+        let carried = queue.split_off(budget);
+
+        for kitty_id in queue {
+            if Self::listing_expiry(kitty_id).map_or(false, |expiry| expiry <= now) {
+                let mut kitty = Self::kitty(kitty_id);
+                if kitty.for_sale {
+                    Self::note_delisted();
+                }
+                kitty.for_sale = false;
+                <Kitties<T>>::insert(kitty_id, kitty);
+                <ListingExpiry<T>>::remove(kitty_id);
+            }
+        }
+
+        if !carried.is_empty() {
+            <ListingExpiryQueue<T>>::mutate(now + One::one(), |queue| queue.extend(carried));
+        }
+    }
+
+    /// Pays the `CREATION_FEE` for `count` kitties from `who` to the treasury, if one is set.
+    fn pay_creation_fee(who: &T::AccountId, count: u32) -> Result {
+        let fee = T::CREATION_FEE.saturating_mul(u64::from(count));
+        Self::pay_treasury(who, fee)
+    }
+
+    /// The `BASE_BREED_FEE` for breeding parents whose older one is `max_parent_gen`, together
+    /// with the treasury it is owed to, or `None` if no treasury is set.
+    fn breed_fee(max_parent_gen: u64) -> rstd::result::Result<Option<(T::AccountId, BalanceOf<T>)>, &'static str> {
+        let fee = max_parent_gen.checked_add(1)
+            .and_then(|multiplier| T::BASE_BREED_FEE.checked_mul(multiplier))
+            .ok_or(Error::Overflow)?;
+        Ok(Self::treasury_fee(fee))
+    }
+
+    /// `fee` together with the treasury it is owed to, or `None` if no treasury is set.
+    fn treasury_fee(fee: u64) -> Option<(T::AccountId, BalanceOf<T>)> {
+        Self::treasury().map(|treasury| (treasury, <BalanceOf<T> as As<u64>>::sa(fee)))
+    }
+
+    fn pay_treasury(who: &T::AccountId, fee: u64) -> Result {
+        let (treasury, fee) = match Self::treasury_fee(fee) {
+            Some(owed) => owed,
+            None => return Ok(()),
+        };
+
+        Self::pay(who, &treasury, fee)?;
+        Self::note_fee_collected(who, treasury, fee);
+
+        Ok(())
+    }
+
+    fn note_fee_collected(who: &T::AccountId, treasury: T::AccountId, fee: BalanceOf<T>) {
+        if !fee.is_zero() && *who != treasury {
+            Self::deposit_event(RawEvent::FeeCollected(treasury, fee));
+        }
+    }
+
+    /// Checks that `pay` would make every one of `payments` from `from`, in order, as far as
+    /// this module can tell without making them, and returns what they cost `from` including the
+    /// currency's fee on each. Callers still have to check `from` can afford the total. A payment
+    /// that creates its recipient's account has to be at least the currency's existential deposit
+    /// and is charged the creation fee rather than the transfer fee, and no recipient's balance
+    /// may overflow.
+    fn ensure_can_pay(
+        from: &T::AccountId,
+        payments: &[(T::AccountId, BalanceOf<T>)],
+    ) -> rstd::result::Result<BalanceOf<T>, &'static str> {
+        let mut total = BalanceOf::<T>::zero();
+        for (i, (to, amount)) in payments.iter().enumerate() {
+            if to == from || amount.is_zero() {
+                continue;
+            }
+            let paid_before = payments[..i].iter()
+                .filter(|(earlier, _)| earlier == to)
+                .try_fold(BalanceOf::<T>::zero(), |sum, (_, paid)| sum.checked_add(paid))
+                .ok_or(Error::Overflow)?;
+            // The currency treats a recipient without free balance as a new account
+            let balance = <T::Currency as Currency<_>>::free_balance(to);
+            let creates_account = balance.is_zero() && paid_before.is_zero();
+            if creates_account {
+                ensure!(
+                    *amount >= <T::Currency as Currency<_>>::minimum_balance(),
+                    Error::BelowExistentialDeposit.as_str()
+                );
+            }
+            balance.checked_add(&paid_before)
+                .and_then(|balance| balance.checked_add(amount))
+                .ok_or(Error::Overflow)?;
+            let fee = if creates_account {
+                T::TransferFees::creation_fee()
+            } else {
+                T::TransferFees::transfer_fee()
+            };
+            total = total.checked_add(amount)
+                .and_then(|total| total.checked_add(&fee))
+                .ok_or(Error::Overflow)?;
+        }
+
+        Ok(total)
+    }
+
+    /// Moves `amount` from `from` to `to`. Paying yourself or paying nothing is a no-op.
+    fn pay(from: &T::AccountId, to: &T::AccountId, amount: BalanceOf<T>) -> Result {
+        if from == to || amount.is_zero() {
+            return Ok(());
+        }
+
+        <T::Currency as Currency<_>>::transfer(from, to, amount)
+    }
+
+    fn cooldown_blocks(gen: u64) -> T::BlockNumber {
+        let blocks = T::CooldownSchedule::cooldown_for_gen(gen);
+        <T::BlockNumber as As<u64>>::sa(u64::from(blocks))
+    }
+
+    fn ensure_can_burn(who: &T::AccountId, kitty_id: T::Hash) -> Result {
+        let owner = Self::owner_of(kitty_id).ok_or(Error::NoOwner)?;
+        ensure!(owner == *who, Error::NotOwner.as_str());
+        ensure!(!Self::is_auctioned(kitty_id), Error::InAuction.as_str());
+        ensure!(!Self::is_leased(kitty_id), Error::Leased.as_str());
+        ensure!(!<Shareholders<T>>::exists(kitty_id), Error::CoOwned.as_str());
+
+        Ok(())
+    }
+
+    fn burn(owner: T::AccountId, kitty_id: T::Hash) -> Result {
+        let owned_kitty_count = Self::owned_kitty_count(&owner);
+        let new_owned_kitty_count = owned_kitty_count.checked_sub(1)
+            .ok_or(Error::Overflow)?;
+
+        let all_kitties_count = Self::all_kitties_count();
+        let new_all_kitties_count = all_kitties_count.checked_sub(1)
+            .ok_or(Error::Overflow)?;
+
+        // "Swap and pop" on the global array, the same way `transfer_from` does it for owners
+        let all_kitties_index = <AllKittiesIndex<T>>::get(kitty_id);
+        if all_kitties_index != new_all_kitties_count {
+            let last_kitty_id = <AllKittiesArray<T>>::get(new_all_kitties_count);
+            <AllKittiesArray<T>>::insert(all_kitties_index, last_kitty_id);
+            <AllKittiesIndex<T>>::insert(last_kitty_id, all_kitties_index);
+        }
+        <AllKittiesArray<T>>::remove(new_all_kitties_count);
+        <AllKittiesIndex<T>>::remove(kitty_id);
+        <AllKittiesCount<T>>::put(new_all_kitties_count);
+
+        // And again on the owner's array
+        let kitty_index = <OwnedKittiesIndex<T>>::get(kitty_id);
+        if kitty_index != new_owned_kitty_count {
+            let last_kitty_id = <OwnedKittiesArray<T>>::get((owner.clone(), new_owned_kitty_count));
+            <OwnedKittiesArray<T>>::insert((owner.clone(), kitty_index), last_kitty_id);
+            <OwnedKittiesIndex<T>>::insert(last_kitty_id, kitty_index);
+        }
+        <OwnedKittiesArray<T>>::remove((owner.clone(), new_owned_kitty_count));
+        <OwnedKittiesIndex<T>>::remove(kitty_id);
+        <OwnedKittiesCount<T>>::insert(&owner, new_owned_kitty_count);
+
+        // And once more on the kitty's generation
+        let kitty = Self::kitty(kitty_id);
+        let gen = kitty.gen;
+        let new_generation_count = Self::generation_count(gen).saturating_sub(1);
+        let generation_index = <GenerationIndex<T>>::get(kitty_id);
+        if generation_index != new_generation_count {
+            let last_kitty_id = <GenerationKitties<T>>::get((gen, new_generation_count));
+            <GenerationKitties<T>>::insert((gen, generation_index), last_kitty_id);
+            <GenerationIndex<T>>::insert(last_kitty_id, generation_index);
+        }
+        <GenerationKitties<T>>::remove((gen, new_generation_count));
+        <GenerationIndex<T>>::remove(kitty_id);
+        <GenerationCount<T>>::insert(gen, new_generation_count);
+
+        if T::REQUIRE_UNIQUE_DNA {
+            <DnaExists<T>>::remove(kitty.dna);
+        }
+        if kitty.for_sale {
+            Self::note_delisted();
+        }
+
+        <Kitties<T>>::remove(kitty_id);
+        <KittyOwner<T>>::remove(kitty_id);
+        <KittyBreeder<T>>::remove(kitty_id);
+        <KittyParents<T>>::remove(kitty_id);
+        <KittyApprovals<T>>::remove(kitty_id);
+        <SireListings<T>>::remove(kitty_id);
+        <ListingExpiry<T>>::remove(kitty_id);
+        <LockedKitties<T>>::remove(kitty_id);
+        <BreedCount<T>>::remove(kitty_id);
+        <NameChangeBlock<T>>::remove(kitty_id);
+        <TransferHistory<T>>::remove(kitty_id);
+        <EliteKitties<T>>::remove(kitty_id);
+        <LastTransferBlock<T>>::remove(kitty_id);
+        <Energy<T>>::remove(kitty_id);
+        <LastEnergyBlock<T>>::remove(kitty_id);
+        <Leases<T>>::remove(kitty_id);
+        <LeaseOffers<T>>::remove(kitty_id);
+
+        Self::deposit_event(RawEvent::Burned(owner, kitty_id));
+
+        Ok(())
+    }
+}
+
+/// tests for this module
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::RefCell;
+    use runtime_io::with_externalities;
+    use primitives::{H256, Blake2Hasher};
+    use support::{impl_outer_origin, impl_outer_event, assert_ok, assert_noop};
+    use support::traits::{ExistenceRequirement, SignedImbalance, UpdateBalanceOutcome, WithdrawReason};
+    use runtime_primitives::{
+        BuildStorage,
+        traits::{BlakeTwo256, IdentityLookup, OnFinalize, OnInitialize},
+        testing::{Digest, DigestItem, Header}
+    };
+
+    impl_outer_origin! {
+        pub enum Origin for Test {}
+    }
+
+    mod kitties {
+        pub use super::super::*;
+    }
+
+    impl_outer_event! {
+        pub enum TestEvent for Test {
+            kitties<T>,
+        }
+    }
+
+    // For testing the module, we construct most of a mock runtime. This means
+    // first constructing a configuration type (`Test`) which `impl`s each of the
+    // configuration traits of modules we want to use.
+    //
+    // `impl_test_runtime!` implements the traits of the modules we depend on, so that
+    // extra runtimes with a different `Trait` configuration stay small.
+    macro_rules! impl_test_runtime {
+        ($runtime:ident) => {
+            impl_test_runtime!($runtime, ());
+        };
+        ($runtime:ident, $event:ty) => {
+            #[derive(Clone, Eq, PartialEq)]
+            pub struct $runtime;
+            impl system::Trait for $runtime {
+                type Origin = Origin;
+                type Index = u64;
+                type BlockNumber = u64;
+                type Hash = H256;
+                type Hashing = BlakeTwo256;
+                type Digest = Digest;
+                type AccountId = u64;
+                type Lookup = IdentityLookup<Self::AccountId>;
+                type Header = Header;
+                type Event = $event;
+                type Log = DigestItem;
+            }
+            impl balances::Trait for $runtime {
+                type Balance = u64;
+                type OnFreeBalanceZero = ();
+                type OnNewAccount = ();
+                type Event = ();
+                type TransactionPayment = ();
+                type DustRemoval = ();
+                type TransferPayment = ();
+            }
+        }
+    }
+
+    // `test_runtime!` builds a whole extra runtime out of just what makes it different: its
+    // `GeneAlgorithm` and `CooldownSchedule` if they aren't the usual ones, then any consts.
+    // Everything else is plain balances with no transfer hooks or restrictions.
+    macro_rules! test_runtime {
+        (@or , $default:ty) => { $default };
+        (@or $given:ty, $default:ty) => { $given };
+        (
+            $runtime:ident {
+                $(type GeneAlgorithm = $genes:ty;)?
+                $(type CooldownSchedule = $cooldown:ty;)?
+                $(const $name:ident: $type:ty = $value:expr;)*
+            }
+        ) => {
+            impl_test_runtime!($runtime);
+            impl Trait for $runtime {
+                type Event = ();
+                type GeneAlgorithm = test_runtime!(@or $($genes)?, XorMixGenes);
+                type OnKittyTransfer = ();
+                type Currency = balances::Module<Self>;
+                type TransferFees = balances::Module<Self>;
+                type AccountValidator = ();
+                type TransferGate = ();
+                type CooldownSchedule = test_runtime!(@or $($cooldown)?, DoublingCooldown);
+                $(const $name: $type = $value;)*
+            }
+        };
+    }
+
+    thread_local! {
+        static LAST_TRANSFER: RefCell<Option<(u64, u64, H256)>> = RefCell::new(None);
+        static PAYMENTS: RefCell<Vec<(u64, u64, u64)>> = RefCell::new(Vec::new());
+    }
+
+    // Remembers the last transfer it was told about.
+    pub struct RecordTransfer;
+    impl OnKittyTransfer<u64, H256> for RecordTransfer {
+        fn on_transfer(from: &u64, to: &u64, kitty_id: H256) {
+            LAST_TRANSFER.with(|last| *last.borrow_mut() = Some((*from, *to, kitty_id)));
+        }
+    }
+
+    fn last_transfer() -> Option<(u64, u64, H256)> {
+        LAST_TRANSFER.with(|last| *last.borrow())
+    }
+
+    // An account that `RejectBlocked` won't let receive kitties.
+    const BLOCKED: u64 = 99;
+
+    pub struct RejectBlocked;
+    impl AccountValidator<u64> for RejectBlocked {
+        fn is_valid_recipient(who: &u64) -> bool {
+            *who != BLOCKED
+        }
+    }
+
+    // An account that hasn't passed `KycGate`.
+    const UNVERIFIED: u64 = 98;
+
+    pub struct KycGate;
+    impl TransferRestriction<u64> for KycGate {
+        fn can_receive(who: &u64) -> bool {
+            *who != UNVERIFIED
+        }
+    }
+
+    type TestBalances = balances::Module<Test>;
+
+    // A currency that keeps its books in `balances` but writes down every transfer, so tests can
+    // see the module pays through `Trait::Currency`.
+    pub struct RecordingCurrency;
+    impl Currency<u64> for RecordingCurrency {
+        type Balance = u64;
+        type PositiveImbalance = <TestBalances as Currency<u64>>::PositiveImbalance;
+        type NegativeImbalance = <TestBalances as Currency<u64>>::NegativeImbalance;
+
+        fn total_balance(who: &u64) -> u64 {
+            TestBalances::total_balance(who)
+        }
+        fn can_slash(who: &u64, value: u64) -> bool {
+            TestBalances::can_slash(who, value)
+        }
+        fn total_issuance() -> u64 {
+            TestBalances::total_issuance()
+        }
+        fn minimum_balance() -> u64 {
+            TestBalances::minimum_balance()
+        }
+        fn free_balance(who: &u64) -> u64 {
+            TestBalances::free_balance(who)
+        }
+        fn ensure_can_withdraw(
+            who: &u64,
+            amount: u64,
+            reason: WithdrawReason,
+            new_balance: u64,
+        ) -> rstd::result::Result<(), &'static str> {
+            TestBalances::ensure_can_withdraw(who, amount, reason, new_balance)
+        }
+        fn transfer(source: &u64, dest: &u64, value: u64) -> rstd::result::Result<(), &'static str> {
+            <TestBalances as Currency<u64>>::transfer(source, dest, value)?;
+            PAYMENTS.with(|payments| payments.borrow_mut().push((*source, *dest, value)));
+            Ok(())
+        }
+        fn withdraw(
+            who: &u64,
+            value: u64,
+            reason: WithdrawReason,
+            liveness: ExistenceRequirement,
+        ) -> rstd::result::Result<Self::NegativeImbalance, &'static str> {
+            TestBalances::withdraw(who, value, reason, liveness)
+        }
+        fn slash(who: &u64, value: u64) -> (Self::NegativeImbalance, u64) {
+            TestBalances::slash(who, value)
+        }
+        fn deposit_into_existing(who: &u64, value: u64) -> rstd::result::Result<Self::PositiveImbalance, &'static str> {
+            TestBalances::deposit_into_existing(who, value)
+        }
+        fn deposit_creating(who: &u64, value: u64) -> Self::PositiveImbalance {
+            TestBalances::deposit_creating(who, value)
+        }
+        fn make_free_balance_be(
+            who: &u64,
+            balance: u64,
+        ) -> (SignedImbalance<u64, Self::PositiveImbalance>, UpdateBalanceOutcome) {
+            TestBalances::make_free_balance_be(who, balance)
+        }
+    }
+    impl ReservableCurrency<u64> for RecordingCurrency {
+        fn can_reserve(who: &u64, value: u64) -> bool {
+            TestBalances::can_reserve(who, value)
+        }
+        fn slash_reserved(who: &u64, value: u64) -> (Self::NegativeImbalance, u64) {
+            TestBalances::slash_reserved(who, value)
+        }
+        fn reserved_balance(who: &u64) -> u64 {
+            TestBalances::reserved_balance(who)
+        }
+        fn reserve(who: &u64, value: u64) -> rstd::result::Result<(), &'static str> {
+            TestBalances::reserve(who, value)
+        }
+        fn unreserve(who: &u64, value: u64) -> u64 {
+            TestBalances::unreserve(who, value)
+        }
+        fn repatriate_reserved(
+            slashed: &u64,
+            beneficiary: &u64,
+            value: u64,
+        ) -> rstd::result::Result<u64, &'static str> {
+            TestBalances::repatriate_reserved(slashed, beneficiary, value)
+        }
+    }
+
+    fn payments() -> Vec<(u64, u64, u64)> {
+        PAYMENTS.with(|payments| payments.borrow().clone())
+    }
+
+    // The main test runtime also records this module's events.
+    impl_test_runtime!(Test, TestEvent);
+    impl Trait for Test {
+        type Event = TestEvent;
+        type GeneAlgorithm = XorMixGenes;
+        type OnKittyTransfer = RecordTransfer;
+        type Currency = RecordingCurrency;
+        type TransferFees = TestBalances;
+        type AccountValidator = RejectBlocked;
+        type TransferGate = KycGate;
+        type CooldownSchedule = DoublingCooldown;
+        const MARKETPLACE_FEE_PERCENT: u32 = 10;
+        const ROYALTY_PERCENT: u32 = 5;
+        const MAX_KITTIES: u64 = 20;
+        const CREATION_FEE: u64 = 5;
+        const BASE_BREED_FEE: u64 = 3;
+        const MAX_HISTORY: u32 = 3;
+        const ELITE_MIN_GEN: u64 = 1;
+        const MAX_BREEDS: u32 = 2;
+        const MAX_EXPIRIES_PER_BLOCK: u32 = 2;
+    }
+
+    // A runtime where breeders have to own both parents.
+    test_runtime!(StrictTest {
+        const BREED_REQUIRES_BOTH_PARENTS: bool = true;
+    });
+
+    // A runtime whose bred kitties always get the same DNA.
+    pub struct FixedDna;
+    impl GeneAlgorithm<H256> for FixedDna {
+        fn combine(_: &H256, _: &H256, _: &H256) -> H256 {
+            H256::repeat_byte(7)
+        }
+    }
+
+    test_runtime!(FixedDnaTest {
+        type GeneAlgorithm = FixedDna;
+    });
+
+    // The same, except that every byte of a bred kitty's DNA mutates.
+    test_runtime!(MutantTest {
+        type GeneAlgorithm = FixedDna;
+        const MUTATION_RATE_PERCENT: u32 = 100;
+    });
+
+    // A runtime where nobody may own more than two kitties.
+    test_runtime!(CappedTest {
+        const MAX_KITTIES_PER_ACCOUNT: u64 = 2;
+    });
+
+    // A runtime where listings must be priced between 10 and 500.
+    test_runtime!(PricedTest {
+        const MIN_LISTING_PRICE: u64 = 10;
+        const MAX_LISTING_PRICE: u64 = 500;
+    });
+
+    // A runtime with only three generation 0 kitties to go around.
+    test_runtime!(ScarceTest {
+        const MAX_GEN0: u64 = 3;
+    });
+
+    // Like `FixedDnaTest`, but no two kitties may share DNA.
+    test_runtime!(UniqueDnaTest {
+        type GeneAlgorithm = FixedDna;
+        const REQUIRE_UNIQUE_DNA: bool = true;
+        const BASE_BREED_FEE: u64 = 3;
+    });
+
+    // A runtime where a kitty can only be renamed every five blocks.
+    test_runtime!(NamingTest {
+        const NAME_COOLDOWN: u64 = 5;
+    });
+
+    // A runtime whose kitties only have two bytes of DNA.
+    test_runtime!(ShortDnaTest {
+        const DNA_BYTES: usize = 2;
+    });
+
+    // A runtime where every parent rests for three blocks, whatever its generation.
+    pub struct ThreeBlockCooldown;
+    impl CooldownSchedule for ThreeBlockCooldown {
+        fn cooldown_for_gen(_: u64) -> u32 {
+            3
+        }
+    }
+
+    test_runtime!(QuickCooldownTest {
+        type CooldownSchedule = ThreeBlockCooldown;
+    });
+
+    // A runtime where every generation 0 kitty has a rare body colour, and half have rare eyes.
+    test_runtime!(RareTraitTest {
+        const RARE_TRAIT_PERCENT: [u32; 4] = [100, 0, 50, 0];
+    });
+
+    // A runtime where a kitty can only change hands every five blocks.
+    test_runtime!(SlowTradeTest {
+        const TRANSFER_COOLDOWN: u64 = 5;
+    });
+
+    // A runtime where only the minters root picks can create kitties.
+    test_runtime!(PermissionedTest {
+        const MINTER_GATE_DISABLED: bool = false;
+    });
+
+    // A runtime where kitties tire after two breedings and get an energy back every ten blocks.
+    test_runtime!(EnergyTest {
+        type CooldownSchedule = ThreeBlockCooldown;
+        const MAX_ENERGY: u32 = 2;
+        const ENERGY_REGEN_BLOCKS: u64 = 10;
+    });
+
+    // A runtime where breeding stops at generation 1.
+    test_runtime!(ShallowTest {
+        const MAX_GENERATION: u64 = 1;
+    });
+
+    // A runtime where only two accounts may have an offer on a kitty at once.
+    test_runtime!(FewOffersTest {
+        const MAX_OFFERS_PER_KITTY: u32 = 2;
+    });
+
+    type System = system::Module<Test>;
+    type Balances = balances::Module<Test>;
+    type Substratekitties = Module<Test>;
+    type StrictSubstratekitties = Module<StrictTest>;
+    type FixedDnaSubstratekitties = Module<FixedDnaTest>;
+    type MutantSubstratekitties = Module<MutantTest>;
+    type CappedSubstratekitties = Module<CappedTest>;
+    type PricedSubstratekitties = Module<PricedTest>;
+    type ScarceSubstratekitties = Module<ScarceTest>;
+    type UniqueDnaSubstratekitties = Module<UniqueDnaTest>;
+    type NamingSubstratekitties = Module<NamingTest>;
+    type ShortDnaSubstratekitties = Module<ShortDnaTest>;
+    type QuickCooldownSubstratekitties = Module<QuickCooldownTest>;
+    type RareTraitSubstratekitties = Module<RareTraitTest>;
+    type SlowTradeSubstratekitties = Module<SlowTradeTest>;
+    type PermissionedSubstratekitties = Module<PermissionedTest>;
+    type EnergySubstratekitties = Module<EnergyTest>;
+    type ShallowSubstratekitties = Module<ShallowTest>;
+    type FewOffersSubstratekitties = Module<FewOffersTest>;
+
+    const ALICE: u64 = 1;
+    const BOB: u64 = 2;
+    const CHARLIE: u64 = 3;
+
+    // Builds a genesis storage where all test accounts start with some funds.
+    fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+        new_test_ext_for::<Test>()
+    }
+
+    fn new_test_ext_for<R>() -> runtime_io::TestExternalities<Blake2Hasher>
+        where R: balances::Trait<AccountId = u64, Balance = u64>
+    {
+        test_storage::<R>().into()
+    }
+
+    fn test_storage<R>() -> runtime_primitives::StorageOverlay
+        where R: balances::Trait<AccountId = u64, Balance = u64>
+    {
+        test_storage_with_fees::<R>(0, 0)
+    }
+
+    // Like `test_storage`, but the currency charges senders `transfer_fee` for every transfer, or
+    // `creation_fee` for one that creates its recipient's account.
+    fn test_storage_with_fees<R>(transfer_fee: u64, creation_fee: u64) -> runtime_primitives::StorageOverlay
+        where R: balances::Trait<AccountId = u64, Balance = u64>
+    {
+        let mut t = system::GenesisConfig::<R>::default().build_storage().unwrap().0;
+        t.extend(balances::GenesisConfig::<R> {
+            balances: vec![(ALICE, 1_000), (BOB, 1_000), (CHARLIE, 1_000)],
+            transaction_base_fee: 0,
+            transaction_byte_fee: 0,
+            existential_deposit: 0,
+            transfer_fee,
+            creation_fee,
+            vesting: vec![],
+        }.build_storage().unwrap().0);
+        t
+    }
+
+    fn new_test_ext_with_fees(transfer_fee: u64, creation_fee: u64) -> runtime_io::TestExternalities<Blake2Hasher> {
+        test_storage_with_fees::<Test>(transfer_fee, creation_fee).into()
+    }
+
+    fn new_test_ext_with_kitties(kitties: Vec<(u64, H256)>) -> runtime_io::TestExternalities<Blake2Hasher> {
+        let mut t = test_storage::<Test>();
+        t.extend(GenesisConfig::<Test> {
+            kitties,
+        }.build_storage().unwrap().0);
+        t.into()
+    }
+
+    // Creates a kitty for `who` and returns its id.
+    fn create_kitty_for(who: u64) -> H256 {
+        assert_ok!(Substratekitties::create_kitty(Origin::signed(who)));
+        let count = Substratekitties::owned_kitty_count(&who);
+        Substratekitties::kitty_of_owner_by_index((who, count - 1))
+    }
+
+    fn create_kitties_for(who: u64, count: u64) -> Vec<H256> {
+        (0..count).map(|_| create_kitty_for(who)).collect()
+    }
+
+    // Asserts that the global and per-owner arrays are contiguous and that every
+    // index map points back to the right slot.
+    fn assert_indices_consistent(owners: &[u64]) {
+        let all_count = Substratekitties::all_kitties_count();
+        for i in 0..all_count {
+            let kitty_id = Substratekitties::kitty_by_index(i);
+            assert!(<Kitties<Test>>::exists(kitty_id));
+            assert_eq!(<AllKittiesIndex<Test>>::get(kitty_id), i);
+        }
+        assert!(!<AllKittiesArray<Test>>::exists(all_count));
+        assert_ok!(Substratekitties::validate_storage());
+
+        for owner in owners {
+            let owned_count = Substratekitties::owned_kitty_count(owner);
+            for i in 0..owned_count {
+                let kitty_id = Substratekitties::kitty_of_owner_by_index((*owner, i));
+                assert_eq!(Substratekitties::owner_of(kitty_id), Some(*owner));
+                assert_eq!(<OwnedKittiesIndex<Test>>::get(kitty_id), i);
+            }
+            assert!(!<OwnedKittiesArray<Test>>::exists((*owner, owned_count)));
+        }
+    }
+
+    fn assert_burned(kitty_id: H256) {
+        assert!(!<Kitties<Test>>::exists(kitty_id));
+        assert_eq!(Substratekitties::owner_of(kitty_id), None);
+        assert!(!<AllKittiesIndex<Test>>::exists(kitty_id));
+        assert!(!<OwnedKittiesIndex<Test>>::exists(kitty_id));
+    }
+
+    fn kitty_events() -> Vec<TestEvent> {
+        System::events().into_iter().map(|record| record.event).collect()
+    }
+
+    fn breed(who: u64, kitty_id_1: H256, kitty_id_2: H256) -> H256 {
+        assert_ok!(Substratekitties::breed_kitty(Origin::signed(who), kitty_id_1, kitty_id_2));
+        let count = Substratekitties::owned_kitty_count(&who);
+        Substratekitties::kitty_of_owner_by_index((who, count - 1))
+    }
+
+    #[test]
+    fn buy_kitty_from_another_account_works() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 0);
+            assert_eq!(Substratekitties::owned_kitty_count(&BOB), 1);
+            assert_eq!(Substratekitties::kitty(kitty_id).price, 0);
+            assert!(!Substratekitties::kitty(kitty_id).for_sale);
+            assert_eq!(Balances::free_balance(&ALICE), 1_100);
+            assert_eq!(Balances::free_balance(&BOB), 900);
+        });
+    }
+
+    #[test]
+    fn buy_own_kitty_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+
+            assert_noop!(
+                Substratekitties::buy_kitty(Origin::signed(ALICE), kitty_id, 100),
+                Error::BuyOwnKitty.as_str()
+            );
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+        });
+    }
+
+    #[test]
+    fn burn_first_kitty_keeps_arrays_contiguous() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 3);
+            create_kitty_for(BOB);
+
+            assert_ok!(Substratekitties::burn_kitty(Origin::signed(ALICE), ids[0]));
+
+            assert_burned(ids[0]);
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 2);
+            assert_eq!(Substratekitties::all_kitties_count(), 3);
+            assert_eq!(Substratekitties::kitty_of_owner_by_index((ALICE, 0)), ids[2]);
+            assert_eq!(Substratekitties::kitty_of_owner_by_index((ALICE, 1)), ids[1]);
+            assert_indices_consistent(&[ALICE, BOB]);
+        });
+    }
+
+    #[test]
+    fn burn_middle_kitty_keeps_arrays_contiguous() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 3);
+
+            assert_ok!(Substratekitties::burn_kitty(Origin::signed(ALICE), ids[1]));
+
+            assert_burned(ids[1]);
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 2);
+            assert_eq!(Substratekitties::all_kitties_count(), 2);
+            assert_eq!(Substratekitties::kitty_of_owner_by_index((ALICE, 0)), ids[0]);
+            assert_eq!(Substratekitties::kitty_of_owner_by_index((ALICE, 1)), ids[2]);
+            assert_indices_consistent(&[ALICE]);
+        });
+    }
+
+    #[test]
+    fn burn_last_kitty_keeps_arrays_contiguous() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 3);
+
+            assert_ok!(Substratekitties::burn_kitty(Origin::signed(ALICE), ids[2]));
+
+            assert_burned(ids[2]);
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 2);
+            assert_eq!(Substratekitties::all_kitties_count(), 2);
+            assert_eq!(Substratekitties::kitty_of_owner_by_index((ALICE, 0)), ids[0]);
+            assert_eq!(Substratekitties::kitty_of_owner_by_index((ALICE, 1)), ids[1]);
+            assert_indices_consistent(&[ALICE]);
+        });
+    }
+
+    #[test]
+    fn burn_kitty_requires_ownership() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::burn_kitty(Origin::signed(BOB), kitty_id),
+                Error::NotOwner.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn set_name_works() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_ok!(Substratekitties::set_name(Origin::signed(ALICE), kitty_id, b"Whiskers".to_vec()));
+            assert_eq!(Substratekitties::kitty(kitty_id).name, b"Whiskers".to_vec());
+
+            assert_ok!(Substratekitties::set_name(Origin::signed(ALICE), kitty_id, b"Mittens".to_vec()));
+            assert_eq!(Substratekitties::kitty(kitty_id).name, b"Mittens".to_vec());
+
+            assert_noop!(
+                Substratekitties::set_name(Origin::signed(ALICE), kitty_id, vec![b'a'; MAX_NAME_LENGTH + 1]),
+                Error::NameTooLong.as_str()
+            );
+            assert_ok!(Substratekitties::set_name(Origin::signed(ALICE), kitty_id, vec![b'a'; MAX_NAME_LENGTH]));
+        });
+    }
+
+    #[test]
+    fn set_name_requires_ownership() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::set_name(Origin::signed(BOB), kitty_id, b"Stolen".to_vec()),
+                Error::NotOwner.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn mint_records_birth_block() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(5);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_eq!(Substratekitties::kitty(kitty_id).birth_block, 5);
+
+            System::set_block_number(8);
+            let other_id = create_kitty_for(ALICE);
+            assert_eq!(Substratekitties::kitty(other_id).birth_block, 8);
+            assert_eq!(Substratekitties::kitty(kitty_id).birth_block, 5);
+        });
+    }
+
+    #[test]
+    fn breeding_puts_parents_to_rest() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let ids = create_kitties_for(ALICE, 2);
+
+            let child = breed(ALICE, ids[0], ids[1]);
+            assert_eq!(Substratekitties::kitty_cooldown(ids[0]), 1 + COOLDOWN_BLOCKS);
+            assert_eq!(Substratekitties::kitty_cooldown(ids[1]), 1 + COOLDOWN_BLOCKS);
+            assert_eq!(Substratekitties::kitty_cooldown(child), 0);
+
+            assert_noop!(
+                Substratekitties::breed_kitty(Origin::signed(ALICE), ids[0], ids[1]),
+                Error::Resting.as_str()
+            );
+
+            System::set_block_number(COOLDOWN_BLOCKS);
+            assert_noop!(
+                Substratekitties::breed_kitty(Origin::signed(ALICE), ids[0], ids[1]),
+                Error::Resting.as_str()
+            );
+
+            System::set_block_number(1 + COOLDOWN_BLOCKS);
+            breed(ALICE, ids[0], ids[1]);
+        });
+    }
+
+    #[test]
+    fn cooldown_grows_with_generation() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let ids = create_kitties_for(ALICE, 3);
+            let child = breed(ALICE, ids[0], ids[1]);
+
+            breed(ALICE, child, ids[2]);
+            assert_eq!(Substratekitties::kitty_cooldown(child), 1 + 2 * COOLDOWN_BLOCKS);
+            assert_eq!(Substratekitties::kitty_cooldown(ids[2]), 1 + COOLDOWN_BLOCKS);
+        });
+    }
+
+    #[test]
+    fn breeding_a_kitty_with_itself_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::breed_kitty(Origin::signed(ALICE), kitty_id, kitty_id),
+                Error::BreedWithSelf.as_str()
+            );
+            assert_eq!(Substratekitties::all_kitties_count(), 1);
+        });
+    }
+
+    #[test]
+    fn breeding_with_one_owned_parent_works() {
+        with_externalities(&mut new_test_ext(), || {
+            let mine = create_kitty_for(ALICE);
+            let theirs = create_kitty_for(BOB);
+
+            let child = breed(ALICE, mine, theirs);
+            assert_eq!(Substratekitties::owner_of(child), Some(ALICE));
+            assert_eq!(Substratekitties::owner_of(theirs), Some(BOB));
+        });
+    }
+
+    #[test]
+    fn breeding_without_owning_a_parent_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 2);
+
+            assert_noop!(
+                Substratekitties::breed_kitty(Origin::signed(BOB), ids[0], ids[1]),
+                Error::NotOwnerOfParent.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn breeding_can_require_both_parents() {
+        with_externalities(&mut new_test_ext_for::<StrictTest>(), || {
+            assert_ok!(StrictSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            assert_ok!(StrictSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            assert_ok!(StrictSubstratekitties::create_kitty(Origin::signed(BOB)));
+            let mine_1 = StrictSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+            let mine_2 = StrictSubstratekitties::kitty_of_owner_by_index((ALICE, 1));
+            let theirs = StrictSubstratekitties::kitty_of_owner_by_index((BOB, 0));
+
+            assert_noop!(
+                StrictSubstratekitties::breed_kitty(Origin::signed(ALICE), mine_1, theirs),
+                Error::NotOwnerOfBothParents.as_str()
+            );
+            assert_ok!(StrictSubstratekitties::breed_kitty(Origin::signed(ALICE), mine_1, mine_2));
+        });
+    }
+
+    #[test]
+    fn transfer_of_nonexistent_kitty_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(ALICE), BOB, H256::random()),
+                Error::KittyNotExists.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn transfer_to_yourself_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(ALICE), ALICE, kitty_id),
+                Error::TransferToSelf.as_str()
+            );
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 1);
+        });
+    }
+
+    #[test]
+    fn approve_requires_ownership() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::approve(Origin::signed(BOB), BOB, kitty_id),
+                Error::NotOwner.as_str()
+            );
+
+            assert_ok!(Substratekitties::approve(Origin::signed(ALICE), BOB, kitty_id));
+            assert_eq!(Substratekitties::approved_account(kitty_id), Some(BOB));
+        });
+    }
+
+    #[test]
+    fn approved_account_can_transfer_once() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(BOB), CHARLIE, kitty_id),
+                Error::NotOwner.as_str()
+            );
+
+            assert_ok!(Substratekitties::approve(Origin::signed(ALICE), BOB, kitty_id));
+            assert_ok!(Substratekitties::transfer(Origin::signed(BOB), CHARLIE, kitty_id));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(CHARLIE));
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 0);
+            assert_eq!(Substratekitties::approved_account(kitty_id), None);
+
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(BOB), ALICE, kitty_id),
+                Error::NotOwner.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn operator_can_transfer_until_revoked() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 2);
+
+            assert_ok!(Substratekitties::set_approval_for_all(Origin::signed(ALICE), BOB, true));
+            assert!(Substratekitties::is_approved_for_all((ALICE, BOB)));
+
+            assert_ok!(Substratekitties::transfer(Origin::signed(BOB), CHARLIE, ids[0]));
+            assert_eq!(Substratekitties::owner_of(ids[0]), Some(CHARLIE));
+
+            assert_ok!(Substratekitties::set_approval_for_all(Origin::signed(ALICE), BOB, false));
+            assert!(!Substratekitties::is_approved_for_all((ALICE, BOB)));
+
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(BOB), CHARLIE, ids[1]),
+                Error::NotOwner.as_str()
+            );
+            assert_eq!(Substratekitties::owner_of(ids[1]), Some(ALICE));
+        });
+    }
+
+    #[test]
+    fn operator_rights_do_not_cover_other_owners() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(CHARLIE);
+            assert_ok!(Substratekitties::set_approval_for_all(Origin::signed(ALICE), BOB, true));
+
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(BOB), ALICE, kitty_id),
+                Error::NotOwner.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn transfer_from_approved_works_for_owner() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_ok!(Substratekitties::transfer_from_approved(Origin::signed(ALICE), ALICE, BOB, kitty_id));
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+        });
+    }
+
+    #[test]
+    fn transfer_from_approved_works_for_approved_account() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::approve(Origin::signed(ALICE), BOB, kitty_id));
+
+            assert_ok!(Substratekitties::transfer_from_approved(Origin::signed(BOB), ALICE, BOB, kitty_id));
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+        });
+    }
+
+    #[test]
+    fn transfer_from_approved_works_for_operator() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_approval_for_all(Origin::signed(ALICE), BOB, true));
+
+            assert_ok!(Substratekitties::transfer_from_approved(Origin::signed(BOB), ALICE, CHARLIE, kitty_id));
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(CHARLIE));
+        });
+    }
+
+    #[test]
+    fn transfer_from_approved_rejects_unauthorized_caller() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::transfer_from_approved(Origin::signed(BOB), ALICE, BOB, kitty_id),
+                Error::NotApproved.as_str()
+            );
+            assert_noop!(
+                Substratekitties::transfer_from_approved(Origin::signed(ALICE), BOB, CHARLIE, kitty_id),
+                Error::FromNotOwner.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn create_kitties_mints_a_batch() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Substratekitties::create_kitties(Origin::signed(ALICE), 5));
+
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 5);
+            assert_eq!(Substratekitties::all_kitties_count(), 5);
+
+            let mut ids: Vec<H256> = (0..5)
+                .map(|i| Substratekitties::kitty_of_owner_by_index((ALICE, i)))
+                .collect();
+            ids.sort();
+            ids.dedup();
+            assert_eq!(ids.len(), 5);
+            assert_indices_consistent(&[ALICE]);
+        });
+    }
+
+    #[test]
+    fn create_kitties_rejects_oversized_batch() {
+        with_externalities(&mut new_test_ext(), || {
+            let max = <Test as Trait>::MAX_BATCH_CREATE;
+
+            assert_noop!(
+                Substratekitties::create_kitties(Origin::signed(ALICE), max + 1),
+                Error::BatchTooLarge.as_str()
+            );
+            assert_eq!(Substratekitties::all_kitties_count(), 0);
+        });
+    }
+
+    #[test]
+    fn create_kitties_mints_nothing_on_overflow() {
+        with_externalities(&mut new_test_ext(), || {
+            <AllKittiesCount<Test>>::put(u64::max_value() - 1);
+
+            assert_noop!(
+                Substratekitties::create_kitties(Origin::signed(ALICE), 2),
+                Error::Overflow.as_str()
+            );
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 0);
+        });
+    }
+
+    #[test]
+    fn batch_transfer_moves_all_kitties() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 3);
+
+            assert_ok!(Substratekitties::batch_transfer(Origin::signed(ALICE), BOB, ids.clone()));
+
+            for kitty_id in &ids {
+                assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+            }
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 0);
+            assert_eq!(Substratekitties::owned_kitty_count(&BOB), 3);
+            assert_indices_consistent(&[ALICE, BOB]);
+        });
+    }
+
+    #[test]
+    fn batch_transfer_reverts_if_any_kitty_is_not_owned() {
+        with_externalities(&mut new_test_ext(), || {
+            let mut ids = create_kitties_for(ALICE, 2);
+            ids.push(create_kitty_for(CHARLIE));
+
+            assert_noop!(
+                Substratekitties::batch_transfer(Origin::signed(ALICE), BOB, ids.clone()),
+                Error::NotOwner.as_str()
+            );
+
+            assert_eq!(Substratekitties::owner_of(ids[0]), Some(ALICE));
+            assert_eq!(Substratekitties::owner_of(ids[1]), Some(ALICE));
+            assert_eq!(Substratekitties::owned_kitty_count(&BOB), 0);
+        });
+    }
+
+    #[test]
+    fn batch_transfer_rejects_oversized_batch() {
+        with_externalities(&mut new_test_ext(), || {
+            let max = <Test as Trait>::MAX_BATCH_TRANSFER as usize;
+
+            assert_noop!(
+                Substratekitties::batch_transfer(Origin::signed(ALICE), BOB, vec![H256::zero(); max + 1]),
+                Error::BatchTooLarge.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn kitties_of_lists_owned_kitties() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_eq!(Substratekitties::kitties_of(&ALICE), Vec::<H256>::new());
+
+            let ids = create_kitties_for(ALICE, 3);
+            create_kitty_for(BOB);
+
+            assert_eq!(Substratekitties::kitties_of(&ALICE), ids);
+        });
+    }
+
+    #[test]
+    fn unlisted_kitty_cannot_be_bought() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert!(!Substratekitties::kitty(kitty_id).for_sale);
+
+            assert_noop!(
+                Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100),
+                Error::NotForSale.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn cancel_sale_keeps_price_but_delists() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+            assert!(Substratekitties::kitty(kitty_id).for_sale);
+
+            assert_noop!(
+                Substratekitties::cancel_sale(Origin::signed(BOB), kitty_id),
+                Error::NotOwner.as_str()
+            );
+            assert_ok!(Substratekitties::cancel_sale(Origin::signed(ALICE), kitty_id));
+
+            let kitty = Substratekitties::kitty(kitty_id);
+            assert!(!kitty.for_sale);
+            assert_eq!(kitty.price, 100);
+            assert_noop!(
+                Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100),
+                Error::NotForSale.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn zero_priced_listed_kitty_can_be_bought() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 0, 0));
+
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 0));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+            assert_eq!(Balances::free_balance(&ALICE), 1_000);
+            assert_eq!(Balances::free_balance(&BOB), 1_000);
+        });
+    }
+
+    #[test]
+    fn buy_kitty_pays_marketplace_fee() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Substratekitties::set_fee_collector(Origin::ROOT, CHARLIE));
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+            assert_eq!(Balances::free_balance(&BOB), 900);
+            assert_eq!(Balances::free_balance(&ALICE), 1_090);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_010);
+        });
+    }
+
+    #[test]
+    fn only_root_sets_fee_collector() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(
+                Substratekitties::set_fee_collector(Origin::signed(ALICE), ALICE),
+                "bad origin: expected to be a root origin"
+            );
+            assert_eq!(Substratekitties::fee_collector(), None);
+        });
+    }
+
+    #[test]
+    fn buy_kitty_fails_without_enough_balance() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Substratekitties::set_fee_collector(Origin::ROOT, CHARLIE));
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 2_000, 0));
+
+            assert_noop!(
+                Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 2_000),
+                Error::InsufficientBalance.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn resale_pays_royalty_to_breeder() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_eq!(Substratekitties::breeder_of(kitty_id), Some(ALICE));
+
+            // Alice selling her own kitty earns no royalty on top
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100));
+            assert_eq!(Balances::free_balance(&ALICE), 1_100);
+            assert_eq!(Balances::free_balance(&BOB), 900);
+
+            assert_ok!(Substratekitties::set_price(Origin::signed(BOB), kitty_id, 200, 0));
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(CHARLIE), kitty_id, 200));
+
+            assert_eq!(Balances::free_balance(&CHARLIE), 800);
+            assert_eq!(Balances::free_balance(&BOB), 1_090);
+            assert_eq!(Balances::free_balance(&ALICE), 1_110);
+            assert_eq!(Substratekitties::breeder_of(kitty_id), Some(ALICE));
+        });
+    }
+
+    #[test]
+    fn kitties_created_in_one_block_get_different_dna() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(3);
+            let first = create_kitty_for(ALICE);
+            let second = create_kitty_for(ALICE);
+
+            assert_ne!(first, second);
+            assert_ne!(Substratekitties::kitty(first).dna, Substratekitties::kitty(second).dna);
+        });
+    }
+
+    #[test]
+    fn genesis_config_seeds_kitties() {
+        let alice_kitty = H256::repeat_byte(1);
+        let bob_kitty = H256::repeat_byte(2);
+
+        with_externalities(&mut new_test_ext_with_kitties(vec![(ALICE, alice_kitty), (BOB, bob_kitty)]), || {
+            assert_eq!(Substratekitties::all_kitties_count(), 2);
+            assert_eq!(Substratekitties::kitty_by_index(0), alice_kitty);
+            assert_eq!(Substratekitties::kitty_by_index(1), bob_kitty);
+
+            assert_eq!(Substratekitties::owner_of(alice_kitty), Some(ALICE));
+            assert_eq!(Substratekitties::owner_of(bob_kitty), Some(BOB));
+            assert_eq!(Substratekitties::kitties_of(&ALICE), vec![alice_kitty]);
+            assert_eq!(Substratekitties::kitties_of(&BOB), vec![bob_kitty]);
+
+            let kitty = Substratekitties::kitty(alice_kitty);
+            assert_eq!(kitty.dna, alice_kitty);
+            assert_eq!(kitty.gen, 0);
+            assert_eq!(kitty.birth_block, 0);
+            assert_indices_consistent(&[ALICE, BOB]);
+
+            assert!(System::events().is_empty());
+        });
+    }
+
+    #[test]
+    fn minting_stops_at_max_kitties() {
+        with_externalities(&mut new_test_ext(), || {
+            let max = <Test as Trait>::MAX_KITTIES;
+            for _ in 0..max {
+                create_kitty_for(ALICE);
+            }
+            assert_eq!(Substratekitties::all_kitties_count(), max);
+
+            assert_noop!(
+                Substratekitties::create_kitty(Origin::signed(BOB)),
+                Error::MaxSupply.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn batch_create_respects_max_kitties() {
+        with_externalities(&mut new_test_ext(), || {
+            let max = <Test as Trait>::MAX_KITTIES;
+            for _ in 0..max - 2 {
+                create_kitty_for(ALICE);
+            }
+
+            assert_noop!(
+                Substratekitties::create_kitties(Origin::signed(ALICE), 3),
+                Error::MaxSupply.as_str()
+            );
+            assert_ok!(Substratekitties::create_kitties(Origin::signed(ALICE), 2));
+        });
+    }
+
+    #[test]
+    fn generation_counts_track_mints_and_burns() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 3);
+            assert_eq!(Substratekitties::generation_count(0), 3);
+            assert_eq!(Substratekitties::generation_count(1), 0);
+
+            let child = breed(ALICE, ids[0], ids[1]);
+            assert_eq!(Substratekitties::kitty(child).gen, 1);
+            assert_eq!(Substratekitties::generation_count(0), 3);
+            assert_eq!(Substratekitties::generation_count(1), 1);
+
+            assert_ok!(Substratekitties::burn_kitty(Origin::signed(ALICE), ids[2]));
+            assert_ok!(Substratekitties::burn_kitty(Origin::signed(ALICE), child));
+            assert_eq!(Substratekitties::generation_count(0), 2);
+            assert_eq!(Substratekitties::generation_count(1), 0);
+        });
+    }
+
+    #[test]
+    fn pedigree_is_recorded_and_walkable() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 3);
+            assert_eq!(Substratekitties::parents_of(ids[0]), (ids[0], ids[0]));
+
+            let child = breed(ALICE, ids[0], ids[1]);
+            assert_eq!(Substratekitties::parents_of(child), (ids[0], ids[1]));
+
+            let grandchild = breed(ALICE, child, ids[2]);
+            assert_eq!(Substratekitties::parents_of(grandchild), (child, ids[2]));
+
+            assert_eq!(Substratekitties::ancestors(grandchild, 0), Vec::<H256>::new());
+            assert_eq!(Substratekitties::ancestors(grandchild, 1), vec![child, ids[2]]);
+            assert_eq!(Substratekitties::ancestors(grandchild, 2), vec![child, ids[2], ids[0], ids[1]]);
+            assert_eq!(Substratekitties::ancestors(grandchild, 10), vec![child, ids[2], ids[0], ids[1]]);
+            assert_eq!(Substratekitties::ancestors(ids[0], 3), Vec::<H256>::new());
+        });
+    }
+
+    #[test]
+    fn breed_with_sire_pays_the_sire_owner() {
+        with_externalities(&mut new_test_ext(), || {
+            let mine = create_kitty_for(ALICE);
+            let sire = create_kitty_for(BOB);
+
+            assert_noop!(
+                Substratekitties::breed_with_sire(Origin::signed(ALICE), mine, sire, 50),
+                Error::NotASire.as_str()
+            );
+            assert_noop!(
+                Substratekitties::set_sire_price(Origin::signed(ALICE), sire, 50),
+                Error::NotOwner.as_str()
+            );
+            assert_ok!(Substratekitties::set_sire_price(Origin::signed(BOB), sire, 50));
+
+            assert_ok!(Substratekitties::breed_with_sire(Origin::signed(ALICE), mine, sire, 50));
+
+            let child = Substratekitties::kitty_of_owner_by_index((ALICE, 1));
+            assert_eq!(Substratekitties::owner_of(child), Some(ALICE));
+            assert_eq!(Substratekitties::parents_of(child), (mine, sire));
+            assert_eq!(Substratekitties::owner_of(sire), Some(BOB));
+            assert_eq!(Balances::free_balance(&ALICE), 950);
+            assert_eq!(Balances::free_balance(&BOB), 1_050);
+        });
+    }
+
+    #[test]
+    fn breed_with_sire_rejects_fee_above_max() {
+        with_externalities(&mut new_test_ext(), || {
+            let mine = create_kitty_for(ALICE);
+            let sire = create_kitty_for(BOB);
+            assert_ok!(Substratekitties::set_sire_price(Origin::signed(BOB), sire, 50));
+
+            assert_noop!(
+                Substratekitties::breed_with_sire(Origin::signed(ALICE), mine, sire, 49),
+                Error::SireFeeTooHigh.as_str()
+            );
+            assert_noop!(
+                Substratekitties::breed_with_sire(Origin::signed(CHARLIE), mine, sire, 50),
+                Error::NotOwner.as_str()
+            );
+            assert_eq!(Balances::free_balance(&ALICE), 1_000);
+        });
+    }
+
+    #[test]
+    fn listing_can_be_bought_before_it_expires() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 5));
+            assert_eq!(Substratekitties::listing_expiry(kitty_id), Some(6));
+
+            System::set_block_number(5);
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100));
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+            assert_eq!(Substratekitties::listing_expiry(kitty_id), None);
+        });
+    }
+
+    #[test]
+    fn expired_listing_cannot_be_bought() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 5));
+
+            System::set_block_number(6);
+            assert_noop!(
+                Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100),
+                Error::ListingExpired.as_str()
+            );
+
+            // Relisting without an expiry makes it buyable again
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+            assert_eq!(Substratekitties::listing_expiry(kitty_id), None);
+            System::set_block_number(100);
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100));
+        });
+    }
+
+    #[test]
+    fn outbid_bidder_is_refunded_and_winner_gets_the_kitty() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::create_auction(Origin::signed(ALICE), kitty_id, 50, 10, 0));
+
+            assert_noop!(
+                Substratekitties::bid(Origin::signed(BOB), kitty_id, 49),
+                Error::BidBelowStart.as_str()
+            );
+            assert_ok!(Substratekitties::bid(Origin::signed(BOB), kitty_id, 100));
+            assert_eq!(Balances::reserved_balance(&BOB), 100);
+            assert_eq!(Balances::free_balance(&BOB), 900);
+
+            assert_noop!(
+                Substratekitties::bid(Origin::signed(CHARLIE), kitty_id, 100),
+                Error::BidTooLow.as_str()
+            );
+            assert_ok!(Substratekitties::bid(Origin::signed(CHARLIE), kitty_id, 150));
+            assert_eq!(Balances::reserved_balance(&BOB), 0);
+            assert_eq!(Balances::free_balance(&BOB), 1_000);
+            assert_eq!(Balances::reserved_balance(&CHARLIE), 150);
+
+            // The kitty can't be moved while it is being auctioned
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id),
+                Error::InAuction.as_str()
+            );
+            assert_noop!(
+                Substratekitties::settle_auction(Origin::signed(BOB), kitty_id),
+                Error::AuctionNotOver.as_str()
+            );
+
+            System::set_block_number(11);
+            assert_noop!(
+                Substratekitties::bid(Origin::signed(BOB), kitty_id, 200),
+                Error::AuctionOver.as_str()
+            );
+            assert_ok!(Substratekitties::settle_auction(Origin::signed(BOB), kitty_id));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(CHARLIE));
+            assert_eq!(Substratekitties::auction(kitty_id), None);
+            assert_eq!(Balances::reserved_balance(&CHARLIE), 0);
+            assert_eq!(Balances::free_balance(&CHARLIE), 850);
+            assert_eq!(Balances::free_balance(&ALICE), 1_150);
+        });
+    }
+
+    #[test]
+    fn auction_without_bids_leaves_kitty_with_seller() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::create_auction(Origin::signed(ALICE), kitty_id, 50, 10, 0));
+
+            System::set_block_number(11);
+            assert_ok!(Substratekitties::settle_auction(Origin::signed(ALICE), kitty_id));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+            assert_eq!(Substratekitties::auction(kitty_id), None);
+            assert_eq!(Balances::free_balance(&ALICE), 1_000);
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id));
+        });
+    }
+
+    #[test]
+    fn auction_below_reserve_does_not_sell() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::create_auction(Origin::signed(ALICE), kitty_id, 50, 10, 200));
+            assert_ok!(Substratekitties::bid(Origin::signed(BOB), kitty_id, 199));
+
+            System::set_block_number(11);
+            assert_ok!(Substratekitties::settle_auction(Origin::signed(BOB), kitty_id));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+            assert_eq!(Substratekitties::auction(kitty_id), None);
+            assert_eq!(Balances::reserved_balance(&BOB), 0);
+            assert_eq!(Balances::free_balance(&BOB), 1_000);
+            assert_eq!(Balances::free_balance(&ALICE), 1_000);
+        });
+    }
+
+    #[test]
+    fn auction_at_reserve_sells() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::create_auction(Origin::signed(ALICE), kitty_id, 50, 10, 200));
+            assert_ok!(Substratekitties::bid(Origin::signed(BOB), kitty_id, 200));
+
+            System::set_block_number(11);
+            assert_ok!(Substratekitties::settle_auction(Origin::signed(BOB), kitty_id));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+            assert_eq!(Balances::free_balance(&BOB), 800);
+            assert_eq!(Balances::free_balance(&ALICE), 1_200);
+        });
+    }
+
+    #[test]
+    fn dutch_price_falls_over_the_auction() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_noop!(
+                Substratekitties::create_dutch_auction(Origin::signed(ALICE), kitty_id, 100, 200, 10),
+                Error::StartBelowEndPrice.as_str()
+            );
+            assert_ok!(Substratekitties::create_dutch_auction(Origin::signed(ALICE), kitty_id, 200, 100, 10));
+
+            assert_eq!(Substratekitties::current_dutch_price(kitty_id), Some(200));
+            System::set_block_number(4);
+            assert_eq!(Substratekitties::current_dutch_price(kitty_id), Some(170));
+            System::set_block_number(6);
+            assert_eq!(Substratekitties::current_dutch_price(kitty_id), Some(150));
+            System::set_block_number(11);
+            assert_eq!(Substratekitties::current_dutch_price(kitty_id), Some(100));
+            System::set_block_number(50);
+            assert_eq!(Substratekitties::current_dutch_price(kitty_id), Some(100));
+
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id),
+                Error::InAuction.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn buy_dutch_pays_the_current_price() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::create_dutch_auction(Origin::signed(ALICE), kitty_id, 200, 100, 10));
+
+            System::set_block_number(6);
+            assert_noop!(
+                Substratekitties::buy_dutch(Origin::signed(BOB), kitty_id, 149),
+                Error::TooExpensive.as_str()
+            );
+            assert_noop!(
+                Substratekitties::buy_dutch(Origin::signed(ALICE), kitty_id, 200),
+                Error::BuyOwnKitty.as_str()
+            );
+            assert_ok!(Substratekitties::buy_dutch(Origin::signed(BOB), kitty_id, 150));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+            assert_eq!(Substratekitties::dutch_auction(kitty_id), None);
+            assert_eq!(Balances::free_balance(&BOB), 850);
+            assert_eq!(Balances::free_balance(&ALICE), 1_150);
+        });
+    }
+
+    #[test]
+    fn cancelled_dutch_auction_frees_the_kitty() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::create_dutch_auction(Origin::signed(ALICE), kitty_id, 200, 100, 10));
+
+            assert_noop!(
+                Substratekitties::cancel_dutch_auction(Origin::signed(BOB), kitty_id),
+                Error::NotOwner.as_str()
+            );
+            assert_ok!(Substratekitties::cancel_dutch_auction(Origin::signed(ALICE), kitty_id));
+
+            assert_noop!(
+                Substratekitties::buy_dutch(Origin::signed(BOB), kitty_id, 200),
+                Error::NotInAuction.as_str()
+            );
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id));
+        });
+    }
+
+    #[test]
+    fn accepted_offer_pays_the_owner() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::make_offer(Origin::signed(ALICE), kitty_id, 100),
+                Error::OfferOnOwnKitty.as_str()
+            );
+            assert_ok!(Substratekitties::make_offer(Origin::signed(BOB), kitty_id, 100));
+            assert_ok!(Substratekitties::make_offer(Origin::signed(CHARLIE), kitty_id, 80));
+            assert_eq!(Balances::reserved_balance(&BOB), 100);
+            assert_eq!(Balances::free_balance(&BOB), 900);
+
+            // Raising an offer only holds the new amount
+            assert_ok!(Substratekitties::make_offer(Origin::signed(BOB), kitty_id, 120));
+            assert_eq!(Balances::reserved_balance(&BOB), 120);
+            assert_eq!(Balances::free_balance(&BOB), 880);
+
+            assert_noop!(
+                Substratekitties::accept_offer(Origin::signed(BOB), kitty_id, BOB),
+                Error::NotOwner.as_str()
+            );
+            assert_ok!(Substratekitties::accept_offer(Origin::signed(ALICE), kitty_id, BOB));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+            assert_eq!(Substratekitties::offer((kitty_id, BOB)), 0);
+            assert_eq!(Balances::reserved_balance(&BOB), 0);
+            assert_eq!(Balances::free_balance(&BOB), 880);
+            assert_eq!(Balances::free_balance(&ALICE), 1_120);
+
+            // The other offer stays in place until its buyer takes it back
+            assert_eq!(Balances::reserved_balance(&CHARLIE), 80);
+            assert_ok!(Substratekitties::withdraw_offer(Origin::signed(CHARLIE), kitty_id));
+            assert_eq!(Balances::reserved_balance(&CHARLIE), 0);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_000);
+        });
+    }
+
+    #[test]
+    fn withdrawn_offer_cannot_be_accepted() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::make_offer(Origin::signed(BOB), kitty_id, 100));
+
+            assert_ok!(Substratekitties::withdraw_offer(Origin::signed(BOB), kitty_id));
+            assert_eq!(Balances::reserved_balance(&BOB), 0);
+            assert_eq!(Balances::free_balance(&BOB), 1_000);
+            assert_noop!(
+                Substratekitties::withdraw_offer(Origin::signed(BOB), kitty_id),
+                Error::NoOfferToWithdraw.as_str()
+            );
+
+            assert_noop!(
+                Substratekitties::accept_offer(Origin::signed(ALICE), kitty_id, BOB),
+                Error::NoOffer.as_str()
+            );
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+        });
+    }
+
+    #[test]
+    fn breeding_uses_the_runtime_gene_algorithm() {
+        with_externalities(&mut new_test_ext_for::<FixedDnaTest>(), || {
+            assert_ok!(FixedDnaSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            assert_ok!(FixedDnaSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            let kitty_id_1 = FixedDnaSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+            let kitty_id_2 = FixedDnaSubstratekitties::kitty_of_owner_by_index((ALICE, 1));
+
+            assert_ok!(FixedDnaSubstratekitties::breed_kitty(Origin::signed(ALICE), kitty_id_1, kitty_id_2));
+            let child_id = FixedDnaSubstratekitties::kitty_of_owner_by_index((ALICE, 2));
+
+            assert_eq!(FixedDnaSubstratekitties::kitty(child_id).dna, H256::repeat_byte(7));
+        });
+    }
+
+    #[test]
+    fn xor_mix_genes_takes_each_byte_from_a_parent() {
+        let dna_1 = H256::repeat_byte(1);
+        let dna_2 = H256::repeat_byte(2);
+        let mut seed = H256::repeat_byte(3);
+        seed.as_mut()[0] = 4;
+        seed.as_mut()[31] = 6;
+
+        let child = <XorMixGenes as GeneAlgorithm<H256>>::combine(&dna_1, &dna_2, &seed);
+
+        assert_eq!(child.as_ref()[0], 2);
+        assert!(child.as_ref()[1..31].iter().all(|b| *b == 1));
+        assert_eq!(child.as_ref()[31], 2);
+    }
+
+    #[test]
+    fn full_mutation_rate_replaces_the_inherited_dna() {
+        with_externalities(&mut new_test_ext_for::<MutantTest>(), || {
+            assert_ok!(MutantSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            assert_ok!(MutantSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            let kitty_id_1 = MutantSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+            let kitty_id_2 = MutantSubstratekitties::kitty_of_owner_by_index((ALICE, 1));
+
+            assert_ok!(MutantSubstratekitties::breed_kitty(Origin::signed(ALICE), kitty_id_1, kitty_id_2));
+            let child_id = MutantSubstratekitties::kitty_of_owner_by_index((ALICE, 2));
+            let dna = MutantSubstratekitties::kitty(child_id).dna;
+
+            assert_ne!(dna, H256::repeat_byte(7));
+            assert_ne!(dna, MutantSubstratekitties::kitty(kitty_id_1).dna);
+            assert_ne!(dna, MutantSubstratekitties::kitty(kitty_id_2).dna);
+        });
+    }
+
+    #[test]
+    fn zero_mutation_rate_only_inherits() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id_1 = create_kitty_for(ALICE);
+            let kitty_id_2 = create_kitty_for(ALICE);
+            let child_id = breed(ALICE, kitty_id_1, kitty_id_2);
+
+            let dna_1 = Substratekitties::kitty(kitty_id_1).dna;
+            let dna_2 = Substratekitties::kitty(kitty_id_2).dna;
+            let dna = Substratekitties::kitty(child_id).dna;
+            for i in 0..32 {
+                assert!(dna[i] == dna_1[i] || dna[i] == dna_2[i]);
+            }
+        });
+    }
+
+    #[test]
+    fn last_created_tracks_the_newest_kitty() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id_1 = create_kitty_for(ALICE);
+            assert_eq!(Substratekitties::last_created_by(ALICE), kitty_id_1);
+
+            let kitty_id_2 = create_kitty_for(ALICE);
+            assert_eq!(Substratekitties::last_created_by(ALICE), kitty_id_2);
+
+            let child_id = breed(ALICE, kitty_id_1, kitty_id_2);
+            assert_eq!(Substratekitties::last_created_by(ALICE), child_id);
+            assert_eq!(Substratekitties::last_created_by(BOB), H256::zero());
+        });
+    }
+
+    #[test]
+    fn batch_transfer_weight_grows_with_the_batch() {
+        let one = Call::<Test>::batch_transfer(BOB, vec![H256::repeat_byte(1)]).weight();
+        let three = Call::<Test>::batch_transfer(BOB, vec![H256::repeat_byte(1); 3]).weight();
+
+        assert_eq!(one, TRANSFER_WEIGHT);
+        assert_eq!(three, 3 * TRANSFER_WEIGHT);
+        assert!(Call::<Test>::breed_kitty(H256::zero(), H256::zero()).weight() > Call::<Test>::transfer(BOB, H256::zero()).weight());
+    }
+
+    #[test]
+    fn locked_kitty_cannot_move_or_breed() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            let mate_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+
+            assert_noop!(Substratekitties::lock_kitty(Origin::signed(BOB), kitty_id), Error::NotOwner.as_str());
+            assert_ok!(Substratekitties::lock_kitty(Origin::signed(ALICE), kitty_id));
+            assert!(Substratekitties::is_locked(kitty_id));
+
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id),
+                Error::Locked.as_str()
+            );
+            assert_noop!(
+                Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100),
+                Error::Locked.as_str()
+            );
+            assert_noop!(
+                Substratekitties::breed_kitty(Origin::signed(ALICE), kitty_id, mate_id),
+                Error::Locked.as_str()
+            );
+            assert_eq!(Balances::free_balance(&BOB), 1_000);
+
+            assert_ok!(Substratekitties::unlock_kitty(Origin::signed(ALICE), kitty_id));
+            assert_noop!(
+                Substratekitties::unlock_kitty(Origin::signed(ALICE), kitty_id),
+                Error::NotLocked.as_str()
+            );
+
+            breed(ALICE, kitty_id, mate_id);
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100));
+            assert_ok!(Substratekitties::transfer(Origin::signed(BOB), CHARLIE, kitty_id));
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(CHARLIE));
+        });
+    }
+
+    #[test]
+    fn paused_module_rejects_calls_until_unpaused() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::set_paused(Origin::signed(ALICE), true),
+                "bad origin: expected to be a root origin"
+            );
+            assert_ok!(Substratekitties::set_paused(Origin::ROOT, true));
+
+            assert_noop!(Substratekitties::create_kitty(Origin::signed(ALICE)), Error::Paused.as_str());
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id),
+                Error::Paused.as_str()
+            );
+            assert_noop!(
+                Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0),
+                Error::Paused.as_str()
+            );
+
+            assert_ok!(Substratekitties::set_paused(Origin::ROOT, false));
+
+            create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id));
+        });
+    }
+
+    #[test]
+    fn minting_stops_at_the_account_cap() {
+        with_externalities(&mut new_test_ext_for::<CappedTest>(), || {
+            assert_noop!(
+                CappedSubstratekitties::create_kitties(Origin::signed(ALICE), 3),
+                Error::OwnKittyLimit.as_str()
+            );
+            assert_ok!(CappedSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            assert_ok!(CappedSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            assert_noop!(
+                CappedSubstratekitties::create_kitty(Origin::signed(ALICE)),
+                Error::OwnKittyLimit.as_str()
+            );
+
+            // Breeding would give ALICE a third kitty
+            let kitty_id_1 = CappedSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+            let kitty_id_2 = CappedSubstratekitties::kitty_of_owner_by_index((ALICE, 1));
+            assert_noop!(
+                CappedSubstratekitties::breed_kitty(Origin::signed(ALICE), kitty_id_1, kitty_id_2),
+                Error::OwnKittyLimit.as_str()
+            );
+            assert_eq!(CappedSubstratekitties::owned_kitty_count(&ALICE), 2);
+        });
+    }
+
+    #[test]
+    fn transfers_stop_at_the_recipient_cap() {
+        with_externalities(&mut new_test_ext_for::<CappedTest>(), || {
+            assert_ok!(CappedSubstratekitties::create_kitties(Origin::signed(ALICE), 2));
+            assert_ok!(CappedSubstratekitties::create_kitties(Origin::signed(BOB), 2));
+            assert_ok!(CappedSubstratekitties::create_kitty(Origin::signed(CHARLIE)));
+            let kitty_id = CappedSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+
+            assert_noop!(
+                CappedSubstratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id),
+                Error::RecipientKittyLimit.as_str()
+            );
+            assert_noop!(
+                CappedSubstratekitties::batch_transfer(Origin::signed(ALICE), CHARLIE, CappedSubstratekitties::kitties_of(&ALICE)),
+                Error::RecipientKittyLimit.as_str()
+            );
+
+            assert_ok!(CappedSubstratekitties::transfer(Origin::signed(ALICE), CHARLIE, kitty_id));
+            assert_eq!(CappedSubstratekitties::owner_of(kitty_id), Some(CHARLIE));
+        });
+    }
+
+    #[test]
+    fn create_fails_cleanly_when_the_nonce_runs_out() {
+        with_externalities(&mut new_test_ext(), || {
+            <Nonce<Test>>::put(u64::max_value() - 1);
+
+            create_kitty_for(ALICE);
+            assert_eq!(<Nonce<Test>>::get(), u64::max_value());
+
+            assert_noop!(Substratekitties::create_kitty(Origin::signed(ALICE)), Error::NonceOverflow.as_str());
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 1);
+        });
+    }
+
+    #[test]
+    fn failures_map_to_error_variants() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            let result = Substratekitties::transfer(Origin::signed(BOB), CHARLIE, kitty_id);
+            assert_eq!(result, Err(Error::NotOwner.into()));
+            let result = Substratekitties::transfer(Origin::signed(ALICE), BOB, H256::zero());
+            assert_eq!(result, Err(Error::KittyNotExists.into()));
+            let result = Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 0);
+            assert_eq!(result, Err(Error::NotForSale.into()));
+        });
+    }
+
+    #[test]
+    fn error_indices_are_stable() {
+        assert_eq!(Error::KittyNotExists.encode(), vec![0]);
+        assert_eq!(Error::NotOwner.encode(), vec![3]);
+        assert_eq!(Error::Paused.encode(), vec![44]);
+        assert_eq!(Error::decode(&mut &[8u8][..]), Some(Error::Overflow));
+    }
+
+    #[test]
+    fn kitties_are_listed_by_generation() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitties = create_kitties_for(ALICE, 3);
+            let child_1 = breed(ALICE, kitties[0], kitties[1]);
+            let child_2 = breed(ALICE, kitties[2], child_1);
+
+            assert_eq!(Substratekitties::kitties_in_generation(0), kitties);
+            assert_eq!(Substratekitties::kitties_in_generation(1), vec![child_1]);
+            assert_eq!(Substratekitties::kitties_in_generation(2), vec![child_2]);
+
+            // Burning the first kitty moves the last one of its generation into its slot
+            assert_ok!(Substratekitties::burn_kitty(Origin::signed(ALICE), kitties[0]));
+            assert_eq!(Substratekitties::kitties_in_generation(0), vec![kitties[2], kitties[1]]);
+            assert_eq!(<GenerationIndex<Test>>::get(kitties[2]), 0);
+            assert!(!<GenerationIndex<Test>>::exists(kitties[0]));
+            assert!(!<GenerationKitties<Test>>::exists((0, 2)));
+
+            assert_ok!(Substratekitties::burn_kitty(Origin::signed(ALICE), child_1));
+            assert_eq!(Substratekitties::kitties_in_generation(1), vec![]);
+            assert_eq!(Substratekitties::kitties_in_generation(2), vec![child_2]);
+        });
+    }
+
+    #[test]
+    fn creation_fee_goes_to_the_treasury() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(
+                Substratekitties::set_treasury(Origin::signed(ALICE), CHARLIE),
+                "bad origin: expected to be a root origin"
+            );
+            assert_ok!(Substratekitties::set_treasury(Origin::ROOT, CHARLIE));
+
+            create_kitty_for(ALICE);
+            assert_eq!(Balances::free_balance(&ALICE), 995);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_005);
+
+            assert_ok!(Substratekitties::create_kitties(Origin::signed(BOB), 2));
+            assert_eq!(Balances::free_balance(&BOB), 990);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_015);
+        });
+    }
+
+    #[test]
+    fn create_without_the_fee_mints_nothing() {
+        with_externalities(&mut new_test_ext(), || {
+            let penniless = 4;
+            assert_ok!(Substratekitties::set_treasury(Origin::ROOT, CHARLIE));
+
+            assert!(Substratekitties::create_kitty(Origin::signed(penniless)).is_err());
+
+            assert_eq!(Substratekitties::owned_kitty_count(&penniless), 0);
+            assert_eq!(Substratekitties::all_kitties_count(), 0);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_000);
+        });
+    }
+
+    #[test]
+    fn attributes_are_read_from_dna() {
+        let mut dna = H256::zero();
+        dna.as_mut()[..5].copy_from_slice(&[12, 3, 200, 7, 99]);
+
+        assert_eq!(Substratekitties::body_color(&dna), 12);
+        assert_eq!(Substratekitties::eye_type(&dna), 3);
+        assert_eq!(Substratekitties::eye_color(&dna), 200);
+        assert_eq!(Substratekitties::pattern(&dna), 7);
+    }
+
+    #[test]
+    fn breeding_emits_bred_as_well_as_created() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id_1 = create_kitty_for(ALICE);
+            let kitty_id_2 = create_kitty_for(BOB);
+            assert!(!kitty_events().iter().any(|event| match *event {
+                TestEvent::kitties(RawEvent::Bred(..)) => true,
+                _ => false,
+            }));
+
+            let child_id = breed(ALICE, kitty_id_1, kitty_id_2);
+
+            let events = kitty_events();
+            assert!(events.contains(&TestEvent::kitties(RawEvent::Created(ALICE, child_id, Substratekitties::kitty(child_id).dna, 2))));
+            assert!(events.contains(&TestEvent::kitties(RawEvent::Bred(ALICE, child_id, kitty_id_1, kitty_id_2))));
+        });
+    }
+
+    #[test]
+    fn kitties_can_be_found_by_dna_prefix() {
+        let mut dna_1 = H256::repeat_byte(1);
+        dna_1.as_mut()[..2].copy_from_slice(&[0xca, 0x75]);
+        let mut dna_2 = H256::repeat_byte(2);
+        dna_2.as_mut()[..2].copy_from_slice(&[0xca, 0x75]);
+        let mut dna_3 = H256::repeat_byte(3);
+        dna_3.as_mut()[0] = 0xca;
+
+        with_externalities(&mut new_test_ext_with_kitties(vec![(ALICE, dna_1), (BOB, dna_2), (ALICE, dna_3)]), || {
+            assert_eq!(Substratekitties::find_by_dna_prefix(&[0xca, 0x75]), vec![dna_1, dna_2]);
+            assert_eq!(Substratekitties::find_by_dna_prefix(&[0xca]), vec![dna_1, dna_2, dna_3]);
+            assert_eq!(Substratekitties::find_by_dna_prefix(&[0xca, 0x75, 0x02]), vec![dna_2]);
+            assert_eq!(Substratekitties::find_by_dna_prefix(&[0x00]), vec![]);
+            assert_eq!(Substratekitties::find_by_dna_prefix(&[0xca; MAX_DNA_PREFIX_LENGTH + 1]), vec![]);
+        });
+    }
+
+    #[test]
+    fn transfer_takes_a_kitty_off_the_market() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 10));
+
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id));
+
+            let kitty = Substratekitties::kitty(kitty_id);
+            assert!(!kitty.for_sale);
+            assert_eq!(kitty.price, 0);
+            assert_eq!(Substratekitties::listing_expiry(kitty_id), None);
+            assert_noop!(
+                Substratekitties::buy_kitty(Origin::signed(CHARLIE), kitty_id, 100),
+                Error::NotForSale.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn kitties_stop_breeding_at_the_limit() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let ids = create_kitties_for(ALICE, 3);
+
+            breed(ALICE, ids[0], ids[1]);
+            System::set_block_number(1 + COOLDOWN_BLOCKS);
+            breed(ALICE, ids[0], ids[2]);
+            assert_eq!(Substratekitties::breed_count(ids[0]), 2);
+            assert_eq!(Substratekitties::breed_count(ids[1]), 1);
+
+            System::set_block_number(1 + 2 * COOLDOWN_BLOCKS);
+            assert_noop!(
+                Substratekitties::breed_kitty(Origin::signed(ALICE), ids[0], ids[1]),
+                Error::Infertile.as_str()
+            );
+            assert_noop!(
+                Substratekitties::breed_kitty(Origin::signed(ALICE), ids[2], ids[0]),
+                Error::Infertile.as_str()
+            );
+            breed(ALICE, ids[1], ids[2]);
+        });
+    }
+
+    #[test]
+    fn free_kitty_can_only_be_claimed_once() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Substratekitties::set_treasury(Origin::ROOT, CHARLIE));
+
+            assert_ok!(Substratekitties::claim_free_kitty(Origin::signed(ALICE)));
+            let kitty_id = Substratekitties::kitty_of_owner_by_index((ALICE, 0));
+            assert!(Substratekitties::has_claimed(&ALICE));
+            assert!(kitty_events().contains(&TestEvent::kitties(RawEvent::FreeKittyClaimed(ALICE, kitty_id))));
+            assert_eq!(Balances::free_balance(&ALICE), 1_000);
+
+            assert_noop!(
+                Substratekitties::claim_free_kitty(Origin::signed(ALICE)),
+                Error::AlreadyClaimed.as_str()
+            );
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 1);
+
+            assert_ok!(Substratekitties::claim_free_kitty(Origin::signed(BOB)));
+        });
+    }
+
+    #[test]
+    fn listing_prices_must_be_in_range() {
+        with_externalities(&mut new_test_ext_for::<PricedTest>(), || {
+            assert_ok!(PricedSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            let kitty_id = PricedSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+
+            assert_noop!(
+                PricedSubstratekitties::set_price(Origin::signed(ALICE), kitty_id, 9, 0),
+                Error::PriceBelowMinimum.as_str()
+            );
+            assert_noop!(
+                PricedSubstratekitties::set_price(Origin::signed(ALICE), kitty_id, 501, 0),
+                Error::PriceAboveMaximum.as_str()
+            );
+
+            assert_ok!(PricedSubstratekitties::set_price(Origin::signed(ALICE), kitty_id, 10, 0));
+            assert_eq!(PricedSubstratekitties::kitty(kitty_id).price, 10);
+            assert_ok!(PricedSubstratekitties::set_price(Origin::signed(ALICE), kitty_id, 500, 0));
+            assert_eq!(PricedSubstratekitties::kitty(kitty_id).price, 500);
+        });
+    }
+
+    #[test]
+    fn owned_value_sums_kitty_prices() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 3);
+            assert_eq!(Substratekitties::owned_value(&ALICE), 0);
+
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[0], 100, 0));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[1], 250, 0));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[2], 0, 0));
+
+            assert_eq!(Substratekitties::owned_value(&ALICE), 350);
+            assert_eq!(Substratekitties::owned_value(&BOB), 0);
+
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[2], u64::max_value(), 0));
+            assert_eq!(Substratekitties::owned_value(&ALICE), u64::max_value());
+        });
+    }
+
+    #[test]
+    fn created_event_carries_the_dna() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            let dna = Substratekitties::kitty(kitty_id).dna;
+
+            assert_eq!(
+                kitty_events().last(),
+                Some(&TestEvent::kitties(RawEvent::Created(ALICE, kitty_id, dna, 1)))
+            );
+        });
+    }
+
+    #[test]
+    fn accepted_swap_trades_both_kitties() {
+        with_externalities(&mut new_test_ext(), || {
+            let mine = create_kitty_for(ALICE);
+            let theirs = create_kitty_for(BOB);
+
+            assert_noop!(
+                Substratekitties::propose_swap(Origin::signed(ALICE), mine, theirs, CHARLIE),
+                Error::SwapStale.as_str()
+            );
+            assert_ok!(Substratekitties::propose_swap(Origin::signed(ALICE), mine, theirs, BOB));
+
+            assert_noop!(
+                Substratekitties::accept_swap(Origin::signed(CHARLIE), 0),
+                Error::NotCounterparty.as_str()
+            );
+            assert_ok!(Substratekitties::accept_swap(Origin::signed(BOB), 0));
+
+            assert_eq!(Substratekitties::owner_of(mine), Some(BOB));
+            assert_eq!(Substratekitties::owner_of(theirs), Some(ALICE));
+            assert_eq!(Substratekitties::swap_proposal(0), None);
+            assert_indices_consistent(&[ALICE, BOB]);
+        });
+    }
+
+    #[test]
+    fn swap_fails_once_a_kitty_has_been_sold() {
+        with_externalities(&mut new_test_ext(), || {
+            let mine = create_kitty_for(ALICE);
+            let theirs = create_kitty_for(BOB);
+            assert_ok!(Substratekitties::propose_swap(Origin::signed(ALICE), mine, theirs, BOB));
+
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), mine, 100, 0));
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(CHARLIE), mine, 100));
+
+            assert_noop!(
+                Substratekitties::accept_swap(Origin::signed(BOB), 0),
+                Error::SwapStale.as_str()
+            );
+            assert_eq!(Substratekitties::owner_of(theirs), Some(BOB));
+
+            assert_ok!(Substratekitties::cancel_swap(Origin::signed(BOB), 0));
+            assert_noop!(
+                Substratekitties::accept_swap(Origin::signed(BOB), 0),
+                Error::NoSwap.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn lapsed_listings_are_taken_down_a_few_per_block() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let ids = create_kitties_for(ALICE, 4);
+            for kitty_id in &ids[..3] {
+                assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), *kitty_id, 100, 4));
+            }
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[3], 100, 20));
+
+            <Substratekitties as OnFinalize<u64>>::on_finalize(4);
+            assert!(Substratekitties::kitty(ids[0]).for_sale);
+
+            // Only two fit in the budget of block 5, so the third waits for block 6
+            <Substratekitties as OnFinalize<u64>>::on_finalize(5);
+            assert!(!Substratekitties::kitty(ids[0]).for_sale);
+            assert!(!Substratekitties::kitty(ids[1]).for_sale);
+            assert_eq!(Substratekitties::listing_expiry(ids[1]), None);
+            assert!(Substratekitties::kitty(ids[2]).for_sale);
+
+            <Substratekitties as OnFinalize<u64>>::on_finalize(6);
+            assert!(!Substratekitties::kitty(ids[2]).for_sale);
+            assert!(!<ListingExpiryQueue<Test>>::exists(6));
+
+            assert!(Substratekitties::kitty(ids[3]).for_sale);
+            assert_eq!(Substratekitties::listing_expiry(ids[3]), Some(21));
+        });
+    }
+
+    #[test]
+    fn relisted_kitty_is_not_taken_down_early() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 4));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+
+            <Substratekitties as OnFinalize<u64>>::on_finalize(5);
+
+            assert!(Substratekitties::kitty(kitty_id).for_sale);
+        });
+    }
+
+    #[test]
+    fn kitty_info_bundles_the_kitty_state() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 10));
+            assert_ok!(Substratekitties::approve(Origin::signed(ALICE), BOB, kitty_id));
+
+            let info = Substratekitties::kitty_info(kitty_id).unwrap();
+
+            assert_eq!(info.kitty, Substratekitties::kitty(kitty_id));
+            assert_eq!(Some(info.owner), Substratekitties::owner_of(kitty_id));
+            assert_eq!(info.approved, Substratekitties::approved_account(kitty_id));
+            assert_eq!(info.approved, Some(BOB));
+            assert!(info.for_sale);
+            assert_eq!(info.listing_expiry, Substratekitties::listing_expiry(kitty_id));
+
+            assert_eq!(Substratekitties::kitty_info(H256::zero()), None);
+        });
+    }
+
+    #[test]
+    fn auctioned_or_locked_kitty_cannot_be_priced() {
+        with_externalities(&mut new_test_ext(), || {
+            let auctioned = create_kitty_for(ALICE);
+            let dutch = create_kitty_for(ALICE);
+            let locked = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::create_auction(Origin::signed(ALICE), auctioned, 50, 10, 0));
+            assert_ok!(Substratekitties::create_dutch_auction(Origin::signed(ALICE), dutch, 200, 100, 10));
+            assert_ok!(Substratekitties::lock_kitty(Origin::signed(ALICE), locked));
+
+            assert_noop!(
+                Substratekitties::set_price(Origin::signed(ALICE), auctioned, 100, 0),
+                Error::InAuction.as_str()
+            );
+            assert_noop!(
+                Substratekitties::set_price(Origin::signed(ALICE), dutch, 100, 0),
+                Error::InAuction.as_str()
+            );
+            assert_noop!(
+                Substratekitties::set_price(Origin::signed(ALICE), locked, 100, 0),
+                Error::Locked.as_str()
+            );
+
+            assert_ok!(Substratekitties::unlock_kitty(Origin::signed(ALICE), locked));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), locked, 100, 0));
+        });
+    }
+
+    #[test]
+    fn gen0_cap_leaves_breeding_open() {
+        with_externalities(&mut new_test_ext_for::<ScarceTest>(), || {
+            assert_noop!(
+                ScarceSubstratekitties::create_kitties(Origin::signed(ALICE), 4),
+                Error::Gen0Limit.as_str()
+            );
+            assert_ok!(ScarceSubstratekitties::create_kitties(Origin::signed(ALICE), 2));
+            assert_ok!(ScarceSubstratekitties::claim_free_kitty(Origin::signed(BOB)));
+            assert_eq!(ScarceSubstratekitties::gen0_count(), 3);
+
+            assert_noop!(ScarceSubstratekitties::create_kitty(Origin::signed(ALICE)), Error::Gen0Limit.as_str());
+            assert_noop!(ScarceSubstratekitties::claim_free_kitty(Origin::signed(CHARLIE)), Error::Gen0Limit.as_str());
+
+            let kitty_id_1 = ScarceSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+            let kitty_id_2 = ScarceSubstratekitties::kitty_of_owner_by_index((ALICE, 1));
+            assert_ok!(ScarceSubstratekitties::breed_kitty(Origin::signed(ALICE), kitty_id_1, kitty_id_2));
+            assert_eq!(ScarceSubstratekitties::owned_kitty_count(&ALICE), 3);
+            assert_eq!(ScarceSubstratekitties::gen0_count(), 3);
+
+            // Burning a generation 0 kitty doesn't make room for a new one
+            assert_ok!(ScarceSubstratekitties::burn_kitty(Origin::signed(BOB), ScarceSubstratekitties::kitty_of_owner_by_index((BOB, 0))));
+            assert_noop!(ScarceSubstratekitties::create_kitty(Origin::signed(BOB)), Error::Gen0Limit.as_str());
+        });
+    }
+
+    #[test]
+    fn watchlist_can_be_added_to_and_removed_from() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 3);
+
+            for kitty_id in &ids {
+                assert_ok!(Substratekitties::watch(Origin::signed(BOB), *kitty_id));
+            }
+            // Watching twice changes nothing
+            assert_ok!(Substratekitties::watch(Origin::signed(BOB), ids[0]));
+            assert_eq!(Substratekitties::watched_kitties(&BOB), ids);
+            assert!(Substratekitties::is_watching((BOB, ids[1])));
+
+            assert_ok!(Substratekitties::unwatch(Origin::signed(BOB), ids[0]));
+            assert_eq!(Substratekitties::watched_kitties(&BOB), vec![ids[2], ids[1]]);
+            assert!(!Substratekitties::is_watching((BOB, ids[0])));
+            assert!(kitty_events().contains(&TestEvent::kitties(RawEvent::Unwatched(BOB, ids[0]))));
+
+            assert_noop!(
+                Substratekitties::watch(Origin::signed(BOB), H256::zero()),
+                Error::KittyNotExists.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn unwatching_an_unwatched_kitty_is_a_no_op() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_ok!(Substratekitties::unwatch(Origin::signed(BOB), kitty_id));
+
+            assert_eq!(Substratekitties::watched_kitty_count(&BOB), 0);
+            assert!(!kitty_events().iter().any(|event| match *event {
+                TestEvent::kitties(RawEvent::Unwatched(..)) => true,
+                _ => false,
+            }));
+        });
+    }
+
+    #[test]
+    fn unique_dna_rejects_a_second_kitty_with_the_same_dna() {
+        with_externalities(&mut new_test_ext_for::<UniqueDnaTest>(), || {
+            assert_ok!(UniqueDnaSubstratekitties::create_kitties(Origin::signed(ALICE), 4));
+            let ids = UniqueDnaSubstratekitties::kitties_of(&ALICE);
+            assert_ok!(UniqueDnaSubstratekitties::set_treasury(Origin::ROOT, CHARLIE));
+
+            assert_ok!(UniqueDnaSubstratekitties::breed_kitty(Origin::signed(ALICE), ids[0], ids[1]));
+            assert!(UniqueDnaSubstratekitties::dna_exists(H256::repeat_byte(7)));
+            assert_eq!(balances::Module::<UniqueDnaTest>::free_balance(&ALICE), 997);
+
+            // Neither the breed fee nor the nonce is spent on a kitty that can't be minted
+            assert_noop!(
+                UniqueDnaSubstratekitties::breed_kitty(Origin::signed(ALICE), ids[2], ids[3]),
+                Error::DnaExists.as_str()
+            );
+            assert_eq!(balances::Module::<UniqueDnaTest>::free_balance(&ALICE), 997);
+            assert_eq!(UniqueDnaSubstratekitties::owned_kitty_count(&ALICE), 5);
+
+            // Once the first child is gone its DNA is free again
+            let child_id = UniqueDnaSubstratekitties::kitty_of_owner_by_index((ALICE, 4));
+            assert_ok!(UniqueDnaSubstratekitties::burn_kitty(Origin::signed(ALICE), child_id));
+            assert_ok!(UniqueDnaSubstratekitties::breed_kitty(Origin::signed(ALICE), ids[2], ids[3]));
+        });
+    }
+
+    #[test]
+    fn duplicate_dna_is_allowed_by_default() {
+        with_externalities(&mut new_test_ext_for::<FixedDnaTest>(), || {
+            assert_ok!(FixedDnaSubstratekitties::create_kitties(Origin::signed(ALICE), 4));
+            let ids = FixedDnaSubstratekitties::kitties_of(&ALICE);
+
+            assert_ok!(FixedDnaSubstratekitties::breed_kitty(Origin::signed(ALICE), ids[0], ids[1]));
+            assert_ok!(FixedDnaSubstratekitties::breed_kitty(Origin::signed(ALICE), ids[2], ids[3]));
+
+            assert_eq!(FixedDnaSubstratekitties::owned_kitty_count(&ALICE), 6);
+            assert!(!FixedDnaSubstratekitties::dna_exists(H256::repeat_byte(7)));
+        });
+    }
+
+    #[test]
+    fn transfers_are_reported_to_the_hook() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id));
+            assert_eq!(last_transfer(), Some((ALICE, BOB, kitty_id)));
+
+            assert_ok!(Substratekitties::set_price(Origin::signed(BOB), kitty_id, 100, 0));
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(CHARLIE), kitty_id, 100));
+            assert_eq!(last_transfer(), Some((BOB, CHARLIE, kitty_id)));
+        });
+    }
+
+    #[test]
+    fn set_prices_reverts_if_any_kitty_is_not_owned() {
+        with_externalities(&mut new_test_ext(), || {
+            let mine = create_kitty_for(ALICE);
+            let theirs = create_kitty_for(BOB);
+
+            assert_noop!(
+                Substratekitties::set_prices(Origin::signed(ALICE), vec![(mine, 10), (theirs, 20)]),
+                Error::NotOwner.as_str()
+            );
+
+            assert!(!Substratekitties::kitty(mine).for_sale);
+            assert!(!Substratekitties::kitty(theirs).for_sale);
+        });
+    }
+
+    #[test]
+    fn set_prices_lists_every_owned_kitty() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 2);
+            let events_before = kitty_events().len();
+
+            assert_ok!(Substratekitties::set_prices(Origin::signed(ALICE), vec![(ids[0], 10), (ids[1], 20)]));
+
+            assert!(Substratekitties::kitty(ids[0]).for_sale);
+            assert_eq!(Substratekitties::kitty(ids[0]).price, 10);
+            assert!(Substratekitties::kitty(ids[1]).for_sale);
+            assert_eq!(Substratekitties::kitty(ids[1]).price, 20);
+            assert_eq!(Substratekitties::listing_expiry(ids[0]), None);
+
+            assert_eq!(kitty_events()[events_before..].to_vec(), vec![
+                TestEvent::kitties(RawEvent::PriceSet(ALICE, ids[0], 10)),
+                TestEvent::kitties(RawEvent::PriceSet(ALICE, ids[1], 20)),
+            ]);
+        });
+    }
+
+    #[test]
+    fn set_prices_rejects_oversized_batch() {
+        with_externalities(&mut new_test_ext(), || {
+            let max = <Test as Trait>::MAX_BATCH_PRICE as usize;
+
+            assert_noop!(
+                Substratekitties::set_prices(Origin::signed(ALICE), vec![(H256::zero(), 10); max + 1]),
+                Error::BatchTooLarge.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn kitty_cannot_be_renamed_during_cooldown() {
+        with_externalities(&mut new_test_ext_for::<NamingTest>(), || {
+            system::Module::<NamingTest>::set_block_number(1);
+            assert_ok!(NamingSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            let kitty_id = NamingSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+
+            assert_ok!(NamingSubstratekitties::set_name(Origin::signed(ALICE), kitty_id, b"Whiskers".to_vec()));
+            assert_eq!(NamingSubstratekitties::name_changed_at(kitty_id), Some(1));
+
+            assert_noop!(
+                NamingSubstratekitties::set_name(Origin::signed(ALICE), kitty_id, b"Mittens".to_vec()),
+                Error::NameRecentlyChanged.as_str()
+            );
+
+            system::Module::<NamingTest>::set_block_number(5);
+            assert_noop!(
+                NamingSubstratekitties::set_name(Origin::signed(ALICE), kitty_id, b"Mittens".to_vec()),
+                Error::NameRecentlyChanged.as_str()
+            );
+
+            system::Module::<NamingTest>::set_block_number(6);
+            assert_ok!(NamingSubstratekitties::set_name(Origin::signed(ALICE), kitty_id, b"Mittens".to_vec()));
+            assert_eq!(NamingSubstratekitties::kitty(kitty_id).name, b"Mittens".to_vec());
+            assert_eq!(NamingSubstratekitties::name_changed_at(kitty_id), Some(6));
+        });
+    }
+
+    #[test]
+    fn best_offer_follows_offers_as_they_change() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_eq!(Substratekitties::best_offer(kitty_id), None);
+
+            assert_ok!(Substratekitties::make_offer(Origin::signed(BOB), kitty_id, 100));
+            assert_eq!(Substratekitties::best_offer(kitty_id), Some((BOB, 100)));
+
+            // A lower offer leaves the best one alone
+            assert_ok!(Substratekitties::make_offer(Origin::signed(CHARLIE), kitty_id, 80));
+            assert_eq!(Substratekitties::best_offer(kitty_id), Some((BOB, 100)));
+
+            assert_ok!(Substratekitties::make_offer(Origin::signed(CHARLIE), kitty_id, 150));
+            assert_eq!(Substratekitties::best_offer(kitty_id), Some((CHARLIE, 150)));
+
+            // Lowering the best offer can hand the top spot back
+            assert_ok!(Substratekitties::make_offer(Origin::signed(CHARLIE), kitty_id, 50));
+            assert_eq!(Substratekitties::best_offer(kitty_id), Some((BOB, 100)));
+        });
+    }
+
+    #[test]
+    fn withdrawing_the_best_offer_finds_the_next_best() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::make_offer(Origin::signed(BOB), kitty_id, 100));
+            assert_ok!(Substratekitties::make_offer(Origin::signed(CHARLIE), kitty_id, 80));
+
+            // Lowering an offer that is not the best changes nothing
+            assert_ok!(Substratekitties::make_offer(Origin::signed(CHARLIE), kitty_id, 60));
+            assert_eq!(Substratekitties::best_offer(kitty_id), Some((BOB, 100)));
+
+            assert_ok!(Substratekitties::withdraw_offer(Origin::signed(BOB), kitty_id));
+            assert_eq!(Substratekitties::best_offer(kitty_id), Some((CHARLIE, 60)));
+            assert_eq!(Substratekitties::offerers_of(kitty_id), vec![CHARLIE]);
+
+            assert_ok!(Substratekitties::withdraw_offer(Origin::signed(CHARLIE), kitty_id));
+            assert_eq!(Substratekitties::best_offer(kitty_id), None);
+            assert!(Substratekitties::offerers_of(kitty_id).is_empty());
+        });
+    }
+
+    #[test]
+    fn accepting_the_best_offer_finds_the_next_best() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::make_offer(Origin::signed(BOB), kitty_id, 100));
+            assert_ok!(Substratekitties::make_offer(Origin::signed(CHARLIE), kitty_id, 80));
+
+            assert_ok!(Substratekitties::accept_offer(Origin::signed(ALICE), kitty_id, BOB));
+
+            assert_eq!(Substratekitties::best_offer(kitty_id), Some((CHARLIE, 80)));
+        });
+    }
+
+    #[test]
+    fn breed_fee_rises_with_the_parents_generation() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let ids = create_kitties_for(ALICE, 5);
+            let gen1_a = breed(ALICE, ids[0], ids[1]);
+            System::set_block_number(11);
+            let gen1_b = breed(ALICE, ids[0], ids[1]);
+            let gen2 = breed(ALICE, gen1_a, gen1_b);
+            assert_eq!(Substratekitties::kitty(gen2).gen, 2);
+
+            // Breeding was free until there was a treasury to pay
+            assert_eq!(Balances::free_balance(&ALICE), 1_000);
+            assert_ok!(Substratekitties::set_treasury(Origin::ROOT, CHARLIE));
+
+            breed(ALICE, ids[2], ids[3]);
+            assert_eq!(Balances::free_balance(&ALICE), 997);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_003);
+
+            let child = breed(ALICE, gen2, ids[4]);
+            assert_eq!(Substratekitties::kitty(child).gen, 3);
+            assert_eq!(Balances::free_balance(&ALICE), 988);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_012);
+        });
+    }
+
+    #[test]
+    fn breed_without_the_fee_mints_nothing() {
+        with_externalities(&mut new_test_ext(), || {
+            let penniless = 4;
+            let ids = create_kitties_for(penniless, 2);
+            assert_ok!(Substratekitties::set_treasury(Origin::ROOT, CHARLIE));
+
+            assert!(Substratekitties::breed_kitty(Origin::signed(penniless), ids[0], ids[1]).is_err());
+
+            assert_eq!(Substratekitties::owned_kitty_count(&penniless), 2);
+            assert_eq!(Substratekitties::breed_count(ids[0]), 0);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_000);
+        });
+    }
+
+    #[test]
+    fn transfer_history_lists_owners_in_order() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_eq!(Substratekitties::transfer_history(kitty_id), vec![(ALICE, 1)]);
+
+            System::set_block_number(2);
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id));
+            System::set_block_number(3);
+            assert_ok!(Substratekitties::transfer(Origin::signed(BOB), CHARLIE, kitty_id));
+
+            assert_eq!(Substratekitties::transfer_history(kitty_id), vec![(ALICE, 1), (BOB, 2), (CHARLIE, 3)]);
+        });
+    }
+
+    #[test]
+    fn transfer_history_drops_the_oldest_owners() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id));
+            assert_ok!(Substratekitties::transfer(Origin::signed(BOB), CHARLIE, kitty_id));
+
+            System::set_block_number(4);
+            assert_ok!(Substratekitties::transfer(Origin::signed(CHARLIE), ALICE, kitty_id));
+
+            let history = Substratekitties::transfer_history(kitty_id);
+            assert_eq!(history.len(), <Test as Trait>::MAX_HISTORY as usize);
+            assert_eq!(history, vec![(BOB, 1), (CHARLIE, 1), (ALICE, 4)]);
+        });
+    }
+
+    #[test]
+    fn created_event_counts_the_owners_kitties() {
+        with_externalities(&mut new_test_ext(), || {
+            let first = create_kitty_for(ALICE);
+            let second = create_kitty_for(ALICE);
+            create_kitty_for(BOB);
+
+            let counts: Vec<_> = kitty_events().into_iter().filter_map(|event| match event {
+                TestEvent::kitties(RawEvent::Created(owner, kitty_id, _, count)) => Some((owner, kitty_id, count)),
+                _ => None,
+            }).collect();
+            assert_eq!(counts[..2].to_vec(), vec![(ALICE, first, 1), (ALICE, second, 2)]);
+            assert_eq!(counts[2].2, 1);
+        });
+    }
+
+    #[test]
+    fn root_can_force_transfer_any_kitty() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            create_kitty_for(BOB);
+
+            assert_ok!(Substratekitties::force_transfer(Origin::ROOT, kitty_id, BOB));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 0);
+            assert_eq!(Substratekitties::owned_kitty_count(&BOB), 2);
+            assert_eq!(Substratekitties::kitty_of_owner_by_index((BOB, 1)), kitty_id);
+            assert_indices_consistent(&[ALICE, BOB]);
+            assert_eq!(
+                kitty_events().last(),
+                Some(&TestEvent::kitties(RawEvent::ForceTransferred(ALICE, BOB, kitty_id)))
+            );
+        });
+    }
+
+    #[test]
+    fn only_root_can_force_transfer() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::force_transfer(Origin::signed(BOB), kitty_id, BOB),
+                "bad origin: expected to be a root origin"
+            );
+            assert_noop!(
+                Substratekitties::force_transfer(Origin::ROOT, kitty_id, ALICE),
+                Error::TransferToSelf.as_str()
+            );
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+        });
+    }
+
+    #[test]
+    fn ownership_needs_the_kitty_to_exist() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_eq!(Substratekitties::ownership(kitty_id), Some(ALICE));
+            assert_eq!(Substratekitties::ownership(H256::zero()), None);
+
+            // An owner without a kitty behind it doesn't count
+            let orphan = H256::repeat_byte(1);
+            <KittyOwner<Test>>::insert(orphan, BOB);
+            assert_eq!(Substratekitties::ownership(orphan), None);
+        });
+    }
+
+    #[test]
+    fn validate_storage_catches_mismatched_maps() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Substratekitties::validate_storage());
+
+            let ids = create_kitties_for(ALICE, 2);
+            create_kitty_for(BOB);
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), BOB, ids[0]));
+            assert_ok!(Substratekitties::burn_kitty(Origin::signed(BOB), ids[0]));
+            assert_ok!(Substratekitties::validate_storage());
+
+            <KittyOwner<Test>>::remove(ids[1]);
+            assert_eq!(Substratekitties::validate_storage(), Err(Error::InconsistentStorage.as_str()));
+
+            <KittyOwner<Test>>::insert(ids[1], BOB);
+            assert_eq!(Substratekitties::validate_storage(), Err(Error::InconsistentStorage.as_str()));
+        });
+    }
+
+    #[test]
+    fn only_the_configured_dna_bytes_are_used() {
+        with_externalities(&mut new_test_ext_for::<ShortDnaTest>(), || {
+            assert_ok!(ShortDnaSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            assert_ok!(ShortDnaSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            let kitty_id_1 = ShortDnaSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+            let kitty_id_2 = ShortDnaSubstratekitties::kitty_of_owner_by_index((ALICE, 1));
+
+            assert_ok!(ShortDnaSubstratekitties::breed_kitty(Origin::signed(ALICE), kitty_id_1, kitty_id_2));
+            let child_id = ShortDnaSubstratekitties::kitty_of_owner_by_index((ALICE, 2));
+
+            let dna_1 = ShortDnaSubstratekitties::kitty(kitty_id_1).dna;
+            let dna_2 = ShortDnaSubstratekitties::kitty(kitty_id_2).dna;
+            let child_dna = ShortDnaSubstratekitties::kitty(child_id).dna;
+            for dna in &[dna_1, dna_2, child_dna] {
+                assert!(dna.as_ref()[2..].iter().all(|byte| *byte == 0));
+            }
+
+            // Each byte the child does have came from one of its parents
+            for i in 0..2 {
+                let byte = child_dna.as_ref()[i];
+                assert!(byte == dna_1.as_ref()[i] || byte == dna_2.as_ref()[i]);
+            }
+        });
+    }
+
+    #[test]
+    fn attributes_past_the_dna_bytes_read_as_zero() {
+        let dna = H256::repeat_byte(9);
+
+        assert_eq!(ShortDnaSubstratekitties::body_color(&dna), 9);
+        assert_eq!(ShortDnaSubstratekitties::eye_type(&dna), 9);
+        assert_eq!(ShortDnaSubstratekitties::eye_color(&dna), 0);
+        assert_eq!(ShortDnaSubstratekitties::pattern(&dna), 0);
+    }
+
+    #[test]
+    fn transfer_clears_the_approval_and_refunds_the_recipients_offer() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::approve(Origin::signed(ALICE), BOB, kitty_id));
+            assert_ok!(Substratekitties::make_offer(Origin::signed(BOB), kitty_id, 50));
+            assert_ok!(Substratekitties::make_offer(Origin::signed(CHARLIE), kitty_id, 100));
+
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), CHARLIE, kitty_id));
+
+            assert_eq!(Substratekitties::approved_account(kitty_id), None);
+            assert_eq!(Substratekitties::offer((kitty_id, CHARLIE)), 0);
+            assert_eq!(Balances::reserved_balance(&CHARLIE), 0);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_000);
+            assert!(kitty_events().contains(&TestEvent::kitties(RawEvent::OfferWithdrawn(CHARLIE, kitty_id))));
+
+            // Anyone else's offer is still there for the new owner to accept
+            assert_eq!(Substratekitties::offer((kitty_id, BOB)), 50);
+            assert_eq!(Substratekitties::best_offer(kitty_id), Some((BOB, 50)));
+            assert_ok!(Substratekitties::accept_offer(Origin::signed(CHARLIE), kitty_id, BOB));
+        });
+    }
+
+    #[test]
+    fn kitties_page_reads_a_slice_of_all_kitties() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 5);
+
+            assert_eq!(Substratekitties::kitties_page(0, 3), ids[..3].to_vec());
+            assert_eq!(Substratekitties::kitties_page(3, 3), ids[3..].to_vec());
+            assert_eq!(Substratekitties::kitties_page(5, 3), Vec::<H256>::new());
+            assert_eq!(Substratekitties::kitties_page(u64::max_value(), u64::max_value()), Vec::<H256>::new());
+        });
+    }
+
+    #[test]
+    fn only_late_generations_can_join_the_elite_pool() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 2);
+            assert_noop!(
+                Substratekitties::register_elite(Origin::signed(ALICE), ids[0]),
+                Error::GenerationTooLow.as_str()
+            );
+
+            let child_id = breed(ALICE, ids[0], ids[1]);
+            assert_noop!(
+                Substratekitties::register_elite(Origin::signed(BOB), child_id),
+                Error::NotOwner.as_str()
+            );
+            assert_ok!(Substratekitties::register_elite(Origin::signed(ALICE), child_id));
+
+            assert!(Substratekitties::is_elite(child_id));
+            assert!(!Substratekitties::is_elite(ids[0]));
+            assert_eq!(kitty_events().last(), Some(&TestEvent::kitties(RawEvent::EliteRegistered(child_id))));
+        });
+    }
+
+    #[test]
+    fn failed_settlement_refunds_the_winner() {
+        with_externalities(&mut new_test_ext_for::<CappedTest>(), || {
+            system::Module::<CappedTest>::set_block_number(1);
+            assert_ok!(CappedSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            let kitty_id = CappedSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+            assert_ok!(CappedSubstratekitties::create_auction(Origin::signed(ALICE), kitty_id, 50, 10, 0));
+            assert_ok!(CappedSubstratekitties::bid(Origin::signed(BOB), kitty_id, 100));
+
+            // By the time the auction ends, the winner has no room for another kitty
+            assert_ok!(CappedSubstratekitties::create_kitties(Origin::signed(BOB), 2));
+            system::Module::<CappedTest>::set_block_number(11);
+            assert_ok!(CappedSubstratekitties::settle_auction(Origin::signed(CHARLIE), kitty_id));
+
+            assert_eq!(CappedSubstratekitties::owner_of(kitty_id), Some(ALICE));
+            assert_eq!(CappedSubstratekitties::auction(kitty_id), None);
+            assert_eq!(balances::Module::<CappedTest>::reserved_balance(&BOB), 0);
+            assert_eq!(balances::Module::<CappedTest>::free_balance(&BOB), 1_000);
+            assert_eq!(balances::Module::<CappedTest>::free_balance(&ALICE), 1_000);
+
+            // The seller is free to sell the kitty again
+            assert_ok!(CappedSubstratekitties::transfer(Origin::signed(ALICE), CHARLIE, kitty_id));
+        });
+    }
+
+    #[test]
+    fn sale_status_reflects_each_kind_of_listing() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let ids = create_kitties_for(ALICE, 4);
+            assert_eq!(Substratekitties::sale_status(ids[0]), SaleStatus::NotForSale);
+
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[0], 100, 0));
+            assert_eq!(Substratekitties::sale_status(ids[0]), SaleStatus::FixedPrice(100));
+            assert_ok!(Substratekitties::cancel_sale(Origin::signed(ALICE), ids[0]));
+            assert_eq!(Substratekitties::sale_status(ids[0]), SaleStatus::NotForSale);
+
+            assert_ok!(Substratekitties::create_auction(Origin::signed(ALICE), ids[1], 50, 10, 0));
+            assert_eq!(Substratekitties::sale_status(ids[1]), SaleStatus::Auction(11));
+
+            assert_ok!(Substratekitties::create_dutch_auction(Origin::signed(ALICE), ids[2], 200, 100, 5));
+            assert_eq!(Substratekitties::sale_status(ids[2]), SaleStatus::Auction(6));
+
+            assert_ok!(Substratekitties::set_sire_price(Origin::signed(ALICE), ids[3], 30));
+            assert_eq!(Substratekitties::sale_status(ids[3]), SaleStatus::Sire(30));
+        });
+    }
+
+    #[test]
+    fn breed_log_records_every_breeding() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let ids = create_kitties_for(ALICE, 4);
+            assert_eq!(Substratekitties::breed_event_count(), 0);
+
+            let child_1 = breed(ALICE, ids[0], ids[1]);
+            System::set_block_number(2);
+            let child_2 = breed(ALICE, ids[2], ids[3]);
+
+            assert_eq!(Substratekitties::breed_event_count(), 2);
+            assert_eq!(Substratekitties::breed_event(0), (ids[0], ids[1], child_1, 1));
+            assert_eq!(Substratekitties::breed_event(1), (ids[2], ids[3], child_2, 2));
+
+            // A failed breeding leaves no entry
+            assert!(Substratekitties::breed_kitty(Origin::signed(ALICE), ids[0], ids[1]).is_err());
+            assert_eq!(Substratekitties::breed_event_count(), 2);
+        });
+    }
+
+    #[test]
+    fn module_account_holds_kitties_until_root_releases_them() {
+        with_externalities(&mut new_test_ext(), || {
+            let module_account = Substratekitties::module_account_id();
+            assert!(module_account != ALICE && module_account != BOB);
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_ok!(Substratekitties::deposit_to_module(Origin::signed(ALICE), kitty_id));
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(module_account));
+            assert_eq!(Substratekitties::owned_kitty_count(&module_account), 1);
+
+            assert_noop!(
+                Substratekitties::release_from_module(Origin::signed(ALICE), kitty_id, ALICE),
+                "bad origin: expected to be a root origin"
+            );
+            assert_ok!(Substratekitties::release_from_module(Origin::ROOT, kitty_id, BOB));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+            assert_eq!(Substratekitties::owned_kitty_count(&module_account), 0);
+            assert_indices_consistent(&[ALICE, BOB, module_account]);
+
+            // Root can only release what the module holds
+            assert_noop!(
+                Substratekitties::release_from_module(Origin::ROOT, kitty_id, ALICE),
+                Error::NotOwner.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn transfer_to_an_invalid_recipient_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(ALICE), BLOCKED, kitty_id),
+                Error::InvalidRecipient.as_str()
+            );
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id));
+        });
+    }
+
+    #[test]
+    fn preview_dna_matches_the_bred_child() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 2);
+            let seed = H256::repeat_byte(3);
+            let preview = Substratekitties::preview_dna(ids[0], ids[1], seed);
+            assert_eq!(Substratekitties::all_kitties_count(), 2);
+
+            let child_id = breed(ALICE, ids[0], ids[1]);
+
+            assert_eq!(Substratekitties::preview_dna(ids[0], ids[1], child_id), Substratekitties::kitty(child_id).dna);
+            assert_eq!(Substratekitties::preview_dna(ids[0], ids[1], seed), preview);
+        });
+    }
+
+    #[test]
+    fn breeding_pause_leaves_trading_alone() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 2);
+            let sire = create_kitty_for(BOB);
+            assert_ok!(Substratekitties::set_sire_price(Origin::signed(BOB), sire, 10));
+
+            assert_noop!(
+                Substratekitties::set_breeding_paused(Origin::signed(ALICE), true),
+                "bad origin: expected to be a root origin"
+            );
+            assert_ok!(Substratekitties::set_breeding_paused(Origin::ROOT, true));
+            assert_eq!(kitty_events().last(), Some(&TestEvent::kitties(RawEvent::BreedingPauseToggled(true))));
+
+            assert_noop!(
+                Substratekitties::breed_kitty(Origin::signed(ALICE), ids[0], ids[1]),
+                Error::BreedingPaused.as_str()
+            );
+            assert_noop!(
+                Substratekitties::breed_with_sire(Origin::signed(ALICE), ids[0], sire, 10),
+                Error::BreedingPaused.as_str()
+            );
+
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[0], 100, 0));
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(CHARLIE), ids[0], 100));
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), BOB, ids[1]));
+
+            assert_ok!(Substratekitties::set_breeding_paused(Origin::ROOT, false));
+            breed(BOB, ids[1], sire);
+        });
+    }
+
+    #[test]
+    fn gift_is_held_until_the_recipient_claims_it() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_ok!(Substratekitties::offer_gift(Origin::signed(ALICE), kitty_id, BOB));
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(Substratekitties::module_account_id()));
+            assert_eq!(Substratekitties::gift_recipient(kitty_id), Some(BOB));
+
+            assert_noop!(
+                Substratekitties::claim_gift(Origin::signed(CHARLIE), kitty_id),
+                Error::NotGiftRecipient.as_str()
+            );
+            assert_noop!(
+                Substratekitties::release_from_module(Origin::ROOT, kitty_id, CHARLIE),
+                Error::GiftHeld.as_str()
+            );
+
+            assert_ok!(Substratekitties::claim_gift(Origin::signed(BOB), kitty_id));
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+            assert_eq!(Substratekitties::gift_recipient(kitty_id), None);
+            assert_eq!(Substratekitties::gift_giver(kitty_id), None);
+            assert_eq!(kitty_events().last(), Some(&TestEvent::kitties(RawEvent::GiftClaimed(BOB, kitty_id))));
+
+            assert_noop!(
+                Substratekitties::claim_gift(Origin::signed(BOB), kitty_id),
+                Error::NoGift.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn giver_can_reclaim_an_unclaimed_gift() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::offer_gift(Origin::signed(ALICE), kitty_id, BOB));
+
+            assert_noop!(
+                Substratekitties::reclaim_gift(Origin::signed(BOB), kitty_id),
+                Error::NotGiftGiver.as_str()
+            );
+            assert_ok!(Substratekitties::reclaim_gift(Origin::signed(ALICE), kitty_id));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+            assert_eq!(Substratekitties::gift_recipient(kitty_id), None);
+            assert_noop!(
+                Substratekitties::claim_gift(Origin::signed(BOB), kitty_id),
+                Error::NoGift.as_str()
+            );
+            assert_indices_consistent(&[ALICE, BOB, Substratekitties::module_account_id()]);
+        });
+    }
+
+    #[test]
+    fn doubling_cooldown_doubles_each_generation() {
+        assert_eq!(DoublingCooldown::cooldown_for_gen(0), 10);
+        assert_eq!(DoublingCooldown::cooldown_for_gen(1), 20);
+        assert_eq!(DoublingCooldown::cooldown_for_gen(3), 80);
+        assert_eq!(DoublingCooldown::cooldown_for_gen(100), u32::max_value());
+    }
+
+    #[test]
+    fn cooldown_comes_from_the_runtimes_schedule() {
+        with_externalities(&mut new_test_ext_for::<QuickCooldownTest>(), || {
+            system::Module::<QuickCooldownTest>::set_block_number(1);
+            assert_ok!(QuickCooldownSubstratekitties::create_kitties(Origin::signed(ALICE), 2));
+            let ids = QuickCooldownSubstratekitties::kitties_of(&ALICE);
+
+            assert_ok!(QuickCooldownSubstratekitties::breed_kitty(Origin::signed(ALICE), ids[0], ids[1]));
+
+            assert_eq!(QuickCooldownSubstratekitties::kitty_cooldown(ids[0]), 4);
+            assert_eq!(QuickCooldownSubstratekitties::kitty_cooldown(ids[1]), 4);
+        });
+    }
+
+    #[test]
+    fn cancel_sale_emits_sale_cancelled() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+
+            assert_ok!(Substratekitties::cancel_sale(Origin::signed(ALICE), kitty_id));
+
+            assert_eq!(
+                kitty_events().last(),
+                Some(&TestEvent::kitties(RawEvent::SaleCancelled(ALICE, kitty_id)))
+            );
+        });
+    }
+
+    #[test]
+    fn gen0_dna_is_biased_towards_rare_traits() {
+        let seeds: Vec<H256> = (0..200u8).map(H256::repeat_byte).collect();
+        let mut rare_eyes = 0;
+
+        for seed in &seeds {
+            let dna = RareTraitSubstratekitties::roll_gen0_dna(*seed);
+            assert!(RareTraitSubstratekitties::body_color(&dna) >= RARE_TRAIT_FLOOR);
+            assert_eq!(RareTraitSubstratekitties::eye_type(&dna), RareTraitSubstratekitties::eye_type(seed));
+            assert_eq!(dna.as_ref()[4..], seed.as_ref()[4..]);
+            if RareTraitSubstratekitties::eye_color(&dna) >= RARE_TRAIT_FLOOR {
+                rare_eyes += 1;
+            }
+        }
+
+        // Far more than the one in sixteen a plain hash would give
+        assert!(rare_eyes > 60 && rare_eyes < 150);
+
+        // Without any weights the DNA is just the seed
+        assert_eq!(Substratekitties::roll_gen0_dna(seeds[1]), seeds[1]);
+    }
+
+    #[test]
+    fn kitty_cannot_change_hands_again_straight_away() {
+        with_externalities(&mut new_test_ext_for::<SlowTradeTest>(), || {
+            system::Module::<SlowTradeTest>::set_block_number(1);
+            assert_ok!(SlowTradeSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            let kitty_id = SlowTradeSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+
+            // Minting doesn't start the cooldown
+            assert_ok!(SlowTradeSubstratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id));
+            assert_eq!(SlowTradeSubstratekitties::last_transfer_block(kitty_id), Some(1));
+
+            assert_noop!(
+                SlowTradeSubstratekitties::transfer(Origin::signed(BOB), ALICE, kitty_id),
+                Error::TransferredTooRecently.as_str()
+            );
+            assert_ok!(SlowTradeSubstratekitties::set_price(Origin::signed(BOB), kitty_id, 100, 0));
+            system::Module::<SlowTradeTest>::set_block_number(5);
+            assert_noop!(
+                SlowTradeSubstratekitties::buy_kitty(Origin::signed(CHARLIE), kitty_id, 100),
+                Error::TransferredTooRecently.as_str()
+            );
+
+            system::Module::<SlowTradeTest>::set_block_number(6);
+            assert_ok!(SlowTradeSubstratekitties::buy_kitty(Origin::signed(CHARLIE), kitty_id, 100));
+            assert_eq!(SlowTradeSubstratekitties::owner_of(kitty_id), Some(CHARLIE));
+            assert_eq!(SlowTradeSubstratekitties::last_transfer_block(kitty_id), Some(6));
+        });
+    }
+
+    #[test]
+    fn for_sale_count_follows_listings() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 3);
+            assert_eq!(Substratekitties::for_sale_count(), 0);
+
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[0], 100, 0));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[1], 100, 0));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[2], 100, 0));
+            assert_eq!(Substratekitties::for_sale_count(), 3);
+
+            // Relisting a listed kitty only changes its price
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[0], 80, 0));
+            assert_eq!(Substratekitties::for_sale_count(), 3);
+
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), ids[0], 80));
+            assert_eq!(Substratekitties::for_sale_count(), 2);
+
+            assert_ok!(Substratekitties::cancel_sale(Origin::signed(ALICE), ids[1]));
+            assert_eq!(Substratekitties::for_sale_count(), 1);
+
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), CHARLIE, ids[2]));
+            assert_eq!(Substratekitties::for_sale_count(), 0);
+        });
+    }
+
+    #[test]
+    fn for_sale_count_drops_when_a_listing_expires_or_is_burned() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let ids = create_kitties_for(ALICE, 2);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[0], 100, 2));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[1], 100, 0));
+
+            <Substratekitties as OnFinalize<u64>>::on_finalize(3);
+            assert_eq!(Substratekitties::for_sale_count(), 1);
+
+            assert_ok!(Substratekitties::burn_kitty(Origin::signed(ALICE), ids[1]));
+            assert_eq!(Substratekitties::for_sale_count(), 0);
+        });
+    }
+
+    #[test]
+    fn root_can_mint_a_chosen_kitty() {
+        with_externalities(&mut new_test_ext(), || {
+            let dna = H256::repeat_byte(42);
+
+            assert_ok!(Substratekitties::force_mint(Origin::ROOT, BOB, dna, 3));
+
+            let kitty_id = Substratekitties::kitty_of_owner_by_index((BOB, 0));
+            let kitty = Substratekitties::kitty(kitty_id);
+            assert_eq!(kitty.dna, dna);
+            assert_eq!(kitty.gen, 3);
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+            assert_eq!(Substratekitties::kitties_in_generation(3), vec![kitty_id]);
+            assert_eq!(
+                kitty_events().last(),
+                Some(&TestEvent::kitties(RawEvent::Created(BOB, kitty_id, dna, 1)))
+            );
+            assert_indices_consistent(&[BOB]);
+        });
+    }
+
+    #[test]
+    fn only_root_can_force_mint() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(
+                Substratekitties::force_mint(Origin::signed(ALICE), ALICE, H256::repeat_byte(42), 0),
+                "bad origin: expected to be a root origin"
+            );
+            assert_eq!(Substratekitties::all_kitties_count(), 0);
+        });
+    }
+
+    #[test]
+    fn rarity_score_weighs_each_trait() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_eq!(Substratekitties::rarity_score(H256::zero()), 0);
+
+            let mut dna = H256::zero();
+            dna.as_mut()[..4].copy_from_slice(&[0x0f, 0x35, 0xf0, 0xff]);
+            assert_ok!(Substratekitties::force_mint(Origin::ROOT, ALICE, dna, 0));
+            let kitty_id = Substratekitties::kitty_of_owner_by_index((ALICE, 0));
+            assert_eq!(Substratekitties::rarity_score(kitty_id), 0 + 3 + 15 + 15);
+
+            // A rarer body colour scores higher, everything else being equal
+            dna.as_mut()[0] = 0xf0;
+            assert_ok!(Substratekitties::force_mint(Origin::ROOT, ALICE, dna, 0));
+            let rarer_id = Substratekitties::kitty_of_owner_by_index((ALICE, 1));
+            assert!(Substratekitties::rarity_score(rarer_id) > Substratekitties::rarity_score(kitty_id));
+        });
+    }
+
+    #[test]
+    fn total_volume_adds_up_every_sale() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let ids = create_kitties_for(ALICE, 2);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[0], 100, 0));
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), ids[0], 100));
+            assert_eq!(Substratekitties::total_volume(), 100);
+
+            assert_ok!(Substratekitties::create_auction(Origin::signed(ALICE), ids[1], 50, 10, 0));
+            assert_ok!(Substratekitties::bid(Origin::signed(CHARLIE), ids[1], 70));
+            System::set_block_number(11);
+            assert_ok!(Substratekitties::settle_auction(Origin::signed(ALICE), ids[1]));
+            assert_eq!(Substratekitties::total_volume(), 170);
+        });
+    }
+
+    #[test]
+    fn total_volume_saturates() {
+        with_externalities(&mut new_test_ext(), || {
+            <TotalVolume<Test>>::put(u64::max_value() - 10);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100));
+
+            assert_eq!(Substratekitties::total_volume(), u64::max_value());
+        });
+    }
+
+    #[test]
+    fn buy_kitty_counts_the_sale_and_the_purchase() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100));
+
+            assert_eq!(Substratekitties::sales_count(&ALICE), 1);
+            assert_eq!(Substratekitties::purchases_count(&BOB), 1);
+            assert_eq!(Substratekitties::sales_count(&BOB), 0);
+            assert_eq!(Substratekitties::purchases_count(&ALICE), 0);
+        });
+    }
+
+    #[test]
+    fn transferring_an_accounts_only_kitty_empties_it() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            create_kitty_for(BOB);
+
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id));
+
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 0);
+            assert!(!<OwnedKittiesArray<Test>>::exists((ALICE, 0)));
+            assert_eq!(Substratekitties::owned_kitty_count(&BOB), 2);
+            assert_eq!(Substratekitties::kitty_of_owner_by_index((BOB, 1)), kitty_id);
+            assert_eq!(<OwnedKittiesIndex<Test>>::get(kitty_id), 1);
+            assert_indices_consistent(&[ALICE, BOB]);
+        });
+    }
+
+    #[test]
+    fn an_emptied_account_can_take_its_only_kitty_back() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id));
+            assert_eq!(Substratekitties::owned_kitty_count(&BOB), 1);
+            assert_eq!(<OwnedKittiesIndex<Test>>::get(kitty_id), 0);
+
+            assert_ok!(Substratekitties::transfer(Origin::signed(BOB), ALICE, kitty_id));
+
+            assert_eq!(Substratekitties::owned_kitty_count(&BOB), 0);
+            assert!(!<OwnedKittiesArray<Test>>::exists((BOB, 0)));
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 1);
+            assert_eq!(Substratekitties::kitty_of_owner_by_index((ALICE, 0)), kitty_id);
+            assert_eq!(<OwnedKittiesIndex<Test>>::get(kitty_id), 0);
+            assert_indices_consistent(&[ALICE, BOB]);
+        });
+    }
+
+    #[test]
+    fn only_allowed_minters_create_kitties_behind_the_gate() {
+        with_externalities(&mut new_test_ext_for::<PermissionedTest>(), || {
+            assert_noop!(
+                PermissionedSubstratekitties::set_minter(Origin::signed(ALICE), ALICE, true),
+                "bad origin: expected to be a root origin"
+            );
+            assert_ok!(PermissionedSubstratekitties::set_minter(Origin::ROOT, ALICE, true));
+
+            assert_ok!(PermissionedSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            assert_eq!(PermissionedSubstratekitties::owned_kitty_count(&ALICE), 1);
+
+            assert_noop!(
+                PermissionedSubstratekitties::create_kitty(Origin::signed(BOB)),
+                Error::NotMinter.as_str()
+            );
+            assert_noop!(
+                PermissionedSubstratekitties::create_kitties(Origin::signed(BOB), 2),
+                Error::NotMinter.as_str()
+            );
+            assert_noop!(
+                PermissionedSubstratekitties::claim_free_kitty(Origin::signed(BOB)),
+                Error::NotMinter.as_str()
+            );
+
+            assert_ok!(PermissionedSubstratekitties::set_minter(Origin::ROOT, ALICE, false));
+            assert_noop!(
+                PermissionedSubstratekitties::create_kitty(Origin::signed(ALICE)),
+                Error::NotMinter.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn anyone_creates_kitties_with_the_gate_disabled() {
+        with_externalities(&mut new_test_ext(), || {
+            assert!(!Substratekitties::is_minter(&BOB));
+
+            assert_ok!(Substratekitties::create_kitty(Origin::signed(BOB)));
+
+            assert_eq!(Substratekitties::owned_kitty_count(&BOB), 1);
+        });
+    }
+
+    #[test]
+    fn image_seed_follows_the_dna() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_eq!(Substratekitties::image_seed(H256::zero()), [0u8; 16]);
+
+            let dna = H256::repeat_byte(0xab);
+            assert_ok!(Substratekitties::force_mint(Origin::ROOT, ALICE, dna, 0));
+            assert_ok!(Substratekitties::force_mint(Origin::ROOT, BOB, dna, 0));
+            let kitty_id = Substratekitties::kitty_of_owner_by_index((ALICE, 0));
+            let twin_id = Substratekitties::kitty_of_owner_by_index((BOB, 0));
+            assert_eq!(Substratekitties::image_seed(kitty_id), [0xab; 16]);
+            assert_eq!(Substratekitties::image_seed(twin_id), Substratekitties::image_seed(kitty_id));
+
+            let mut other_dna = dna;
+            other_dna.as_mut()[15] = 0;
+            assert_ok!(Substratekitties::force_mint(Origin::ROOT, ALICE, other_dna, 0));
+            let other_id = Substratekitties::kitty_of_owner_by_index((ALICE, 1));
+            assert_ne!(Substratekitties::image_seed(other_id), Substratekitties::image_seed(kitty_id));
+        });
+    }
+
+    #[test]
+    fn sales_and_fees_are_paid_through_the_configured_currency() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Substratekitties::set_treasury(Origin::ROOT, CHARLIE));
+            let kitty_id = create_kitty_for(ALICE);
+            assert_eq!(payments(), vec![(ALICE, CHARLIE, 5)]);
+
+            assert_ok!(Substratekitties::set_fee_collector(Origin::ROOT, CHARLIE));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100));
+
+            assert_eq!(payments(), vec![(ALICE, CHARLIE, 5), (BOB, CHARLIE, 10), (BOB, ALICE, 90)]);
+        });
+    }
+
+    #[test]
+    fn burn_kitties_burns_the_whole_batch() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 3);
+            let kept = create_kitty_for(ALICE);
+
+            assert_ok!(Substratekitties::burn_kitties(Origin::signed(ALICE), ids.clone()));
+
+            for kitty_id in &ids {
+                assert!(!<Kitties<Test>>::exists(kitty_id));
+            }
+            assert_eq!(Substratekitties::kitties_of(&ALICE), vec![kept]);
+            assert_eq!(Substratekitties::all_kitties_count(), 1);
+            assert_indices_consistent(&[ALICE]);
+        });
+    }
+
+    #[test]
+    fn burn_kitties_reverts_if_any_kitty_is_not_owned() {
+        with_externalities(&mut new_test_ext(), || {
+            let mut ids = create_kitties_for(ALICE, 2);
+            ids.push(create_kitty_for(BOB));
+
+            assert_noop!(
+                Substratekitties::burn_kitties(Origin::signed(ALICE), ids.clone()),
+                Error::NotOwner.as_str()
+            );
+            assert_noop!(
+                Substratekitties::burn_kitties(Origin::signed(ALICE), vec![ids[0], ids[0]]),
+                Error::DuplicateInBatch.as_str()
+            );
+            let max = <Test as Trait>::MAX_BATCH_BURN as usize;
+            assert_noop!(
+                Substratekitties::burn_kitties(Origin::signed(ALICE), vec![ids[0]; max + 1]),
+                Error::BatchTooLarge.as_str()
+            );
+
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 2);
+            assert_eq!(Substratekitties::owned_kitty_count(&BOB), 1);
+            assert_eq!(Substratekitties::all_kitties_count(), 3);
+        });
+    }
+
+    #[test]
+    fn transfer_gate_refuses_unpermitted_recipients() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(ALICE), UNVERIFIED, kitty_id),
+                Error::RecipientNotPermitted.as_str()
+            );
+
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+            assert_noop!(
+                Substratekitties::buy_kitty(Origin::signed(UNVERIFIED), kitty_id, 100),
+                Error::RecipientNotPermitted.as_str()
+            );
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(BOB), kitty_id, 100));
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(BOB));
+        });
+    }
+
+    #[test]
+    fn minting_emits_minted_with_the_generation() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id_1 = create_kitty_for(ALICE);
+            let kitty_id_2 = create_kitty_for(BOB);
+            let events = kitty_events();
+            assert!(events.contains(&TestEvent::kitties(RawEvent::Minted(ALICE, kitty_id_1, 0))));
+            assert!(events.contains(&TestEvent::kitties(RawEvent::Created(ALICE, kitty_id_1, Substratekitties::kitty(kitty_id_1).dna, 1))));
+
+            let child_id = breed(ALICE, kitty_id_1, kitty_id_2);
+
+            let events = kitty_events();
+            assert!(events.contains(&TestEvent::kitties(RawEvent::Minted(ALICE, child_id, 1))));
+            assert!(events.contains(&TestEvent::kitties(RawEvent::Created(ALICE, child_id, Substratekitties::kitty(child_id).dna, 2))));
+        });
+    }
+
+    #[test]
+    fn root_withdraws_from_the_treasury() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_noop!(
+                Substratekitties::withdraw_treasury(Origin::ROOT, ALICE, 100),
+                Error::NoTreasury.as_str()
+            );
+            assert_ok!(Substratekitties::set_treasury(Origin::ROOT, CHARLIE));
+            assert_noop!(
+                Substratekitties::withdraw_treasury(Origin::signed(CHARLIE), ALICE, 100),
+                "bad origin: expected to be a root origin"
+            );
+
+            assert_ok!(Substratekitties::withdraw_treasury(Origin::ROOT, ALICE, 300));
+
+            assert_eq!(Balances::free_balance(&CHARLIE), 700);
+            assert_eq!(Balances::free_balance(&ALICE), 1_300);
+            assert_eq!(
+                kitty_events().last(),
+                Some(&TestEvent::kitties(RawEvent::TreasuryWithdrawn(ALICE, 300)))
+            );
+        });
+    }
+
+    #[test]
+    fn treasury_withdrawal_cannot_exceed_its_balance() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Substratekitties::set_treasury(Origin::ROOT, CHARLIE));
+
+            assert_noop!(
+                Substratekitties::withdraw_treasury(Origin::ROOT, ALICE, 1_001),
+                Error::TreasuryTooLow.as_str()
+            );
+
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_000);
+            assert_eq!(Balances::free_balance(&ALICE), 1_000);
+        });
+    }
+
+    #[test]
+    fn breeding_uses_energy_until_kitties_are_too_tired() {
+        with_externalities(&mut new_test_ext_for::<EnergyTest>(), || {
+            assert_ok!(EnergySubstratekitties::create_kitties(Origin::signed(ALICE), 2));
+            let mum = EnergySubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+            let dad = EnergySubstratekitties::kitty_of_owner_by_index((ALICE, 1));
+            assert_eq!(EnergySubstratekitties::energy(mum), 2);
+
+            system::Module::<EnergyTest>::set_block_number(1);
+            assert_ok!(EnergySubstratekitties::breed_kitty(Origin::signed(ALICE), mum, dad));
+            assert_eq!(EnergySubstratekitties::energy(mum), 1);
+            assert_eq!(EnergySubstratekitties::energy(dad), 1);
+
+            system::Module::<EnergyTest>::set_block_number(4);
+            assert_ok!(EnergySubstratekitties::breed_kitty(Origin::signed(ALICE), mum, dad));
+            assert_eq!(EnergySubstratekitties::energy(mum), 0);
+
+            system::Module::<EnergyTest>::set_block_number(7);
+            assert_noop!(
+                EnergySubstratekitties::breed_kitty(Origin::signed(ALICE), mum, dad),
+                Error::TooTired.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn energy_comes_back_over_time() {
+        with_externalities(&mut new_test_ext_for::<EnergyTest>(), || {
+            assert_ok!(EnergySubstratekitties::create_kitties(Origin::signed(ALICE), 2));
+            let mum = EnergySubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+            let dad = EnergySubstratekitties::kitty_of_owner_by_index((ALICE, 1));
+            system::Module::<EnergyTest>::set_block_number(1);
+            assert_ok!(EnergySubstratekitties::breed_kitty(Origin::signed(ALICE), mum, dad));
+            system::Module::<EnergyTest>::set_block_number(4);
+            assert_ok!(EnergySubstratekitties::breed_kitty(Origin::signed(ALICE), mum, dad));
+
+            system::Module::<EnergyTest>::set_block_number(13);
+            assert_eq!(EnergySubstratekitties::energy(mum), 0);
+            system::Module::<EnergyTest>::set_block_number(14);
+            assert_eq!(EnergySubstratekitties::energy(mum), 1);
+            assert_ok!(EnergySubstratekitties::breed_kitty(Origin::signed(ALICE), mum, dad));
+            assert_eq!(EnergySubstratekitties::energy(mum), 0);
+
+            // Never more than a full rest's worth, however long it has been
+            system::Module::<EnergyTest>::set_block_number(1_000);
+            assert_eq!(EnergySubstratekitties::energy(mum), 2);
+        });
+    }
+
+    #[test]
+    fn leased_kitty_is_used_by_the_lessee() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_eq!(Substratekitties::current_user(kitty_id), Some(ALICE));
+
+            assert_noop!(
+                Substratekitties::lease_kitty(Origin::signed(BOB), kitty_id, CHARLIE, 10, 50),
+                Error::NotOwner.as_str()
+            );
+            assert_ok!(Substratekitties::lease_kitty(Origin::signed(ALICE), kitty_id, BOB, 10, 50));
+            assert_noop!(
+                Substratekitties::accept_lease(Origin::signed(CHARLIE), kitty_id),
+                Error::NoLeaseOffer.as_str()
+            );
+            assert_eq!(Substratekitties::current_user(kitty_id), Some(ALICE));
+
+            assert_ok!(Substratekitties::accept_lease(Origin::signed(BOB), kitty_id));
+
+            assert_eq!(Substratekitties::current_user(kitty_id), Some(BOB));
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+            assert_eq!(Substratekitties::lease(kitty_id), Some((BOB, 11)));
+            assert_eq!(Substratekitties::lease_offer(kitty_id), None);
+            assert_eq!(Balances::free_balance(&ALICE), 1_050);
+            assert_eq!(Balances::free_balance(&BOB), 950);
+        });
+    }
+
+    #[test]
+    fn lease_expiry_hands_the_kitty_back_to_its_owner() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::lease_kitty(Origin::signed(ALICE), kitty_id, BOB, 10, 0));
+            assert_ok!(Substratekitties::accept_lease(Origin::signed(BOB), kitty_id));
+
+            System::set_block_number(10);
+            assert_eq!(Substratekitties::current_user(kitty_id), Some(BOB));
+
+            System::set_block_number(11);
+            assert!(!Substratekitties::is_leased(kitty_id));
+            assert_eq!(Substratekitties::current_user(kitty_id), Some(ALICE));
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), CHARLIE, kitty_id));
+            assert_eq!(Substratekitties::lease(kitty_id), None);
+        });
+    }
+
+    #[test]
+    fn leased_kitty_cannot_change_hands() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::lease_kitty(Origin::signed(ALICE), kitty_id, BOB, 10, 0));
+            assert_ok!(Substratekitties::accept_lease(Origin::signed(BOB), kitty_id));
+
+            assert_noop!(
+                Substratekitties::transfer(Origin::signed(ALICE), CHARLIE, kitty_id),
+                Error::Leased.as_str()
+            );
+            assert_noop!(
+                Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0),
+                Error::Leased.as_str()
+            );
+            assert_noop!(
+                Substratekitties::create_auction(Origin::signed(ALICE), kitty_id, 50, 10, 0),
+                Error::Leased.as_str()
+            );
+            assert_noop!(
+                Substratekitties::make_offer(Origin::signed(CHARLIE), kitty_id, 100),
+                Error::Leased.as_str()
+            );
+            assert_noop!(
+                Substratekitties::burn_kitty(Origin::signed(ALICE), kitty_id),
+                Error::Leased.as_str()
+            );
+            assert_noop!(
+                Substratekitties::lease_kitty(Origin::signed(ALICE), kitty_id, CHARLIE, 10, 0),
+                Error::Leased.as_str()
+            );
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+        });
+    }
+
+    #[test]
+    fn breeding_stops_at_the_max_generation() {
+        with_externalities(&mut new_test_ext_for::<ShallowTest>(), || {
+            assert_ok!(ShallowSubstratekitties::create_kitties(Origin::signed(ALICE), 4));
+            let ids = ShallowSubstratekitties::kitties_of(&ALICE);
+
+            assert_ok!(ShallowSubstratekitties::breed_kitty(Origin::signed(ALICE), ids[0], ids[1]));
+            let child_id = ShallowSubstratekitties::kitty_of_owner_by_index((ALICE, 4));
+            assert_eq!(ShallowSubstratekitties::kitty(child_id).gen, 1);
+
+            assert_noop!(
+                ShallowSubstratekitties::breed_kitty(Origin::signed(ALICE), child_id, ids[2]),
+                Error::MaxGenerationReached.as_str()
+            );
+
+            // Parents of a lower generation can still breed
+            assert_ok!(ShallowSubstratekitties::breed_kitty(Origin::signed(ALICE), ids[2], ids[3]));
+            assert_eq!(ShallowSubstratekitties::owned_kitty_count(&ALICE), 6);
+        });
+    }
+
+    #[test]
+    fn migration_upgrades_version_0_kitties() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 4);
+            // The last kitty is already in the current layout
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[3], 60, 0));
+            let current = Substratekitties::kitty(ids[3]);
+            // Put the others back the way the version 0 runtime encoded them, the third one
+            // as bytes that were never a kitty
+            let dna = Substratekitties::kitty(ids[0]).dna;
+            support::storage::put(&<Kitties<Test>>::key_for(&ids[0]), &(ids[0], dna, 0u64, 0u64));
+            support::storage::put(&<Kitties<Test>>::key_for(&ids[1]), &(ids[1], dna, 40u64, 2u64));
+            support::storage::put(&<Kitties<Test>>::key_for(&ids[2]), &7u8);
+            <StorageVersion<Test>>::put(0);
+
+            System::set_block_number(7);
+            <Substratekitties as OnInitialize<u64>>::on_initialize(7);
+
+            assert_eq!(Substratekitties::storage_version(), STORAGE_VERSION);
+            assert_eq!(Substratekitties::kitty(ids[0]), Kitty {
+                id: ids[0],
+                dna,
+                price: 0,
+                gen: 0,
+                name: Vec::new(),
+                birth_block: 7,
+                for_sale: false,
+            });
+            assert_eq!(Substratekitties::kitty(ids[1]), Kitty {
+                id: ids[1],
+                dna,
+                price: 40,
+                gen: 2,
+                name: Vec::new(),
+                birth_block: 7,
+                for_sale: true,
+            });
+            assert_eq!(Substratekitties::kitty(ids[3]), current);
+            assert_eq!(Substratekitties::for_sale_count(), 2);
+
+            let events = kitty_events();
+            assert!(events.contains(&TestEvent::kitties(RawEvent::KittyMigrationFailed(ids[2]))));
+            assert_eq!(events.last(), Some(&TestEvent::kitties(RawEvent::KittiesMigrated(2, 1))));
+
+            // Once up to date, later blocks leave the kitties alone
+            System::set_block_number(8);
+            <Substratekitties as OnInitialize<u64>>::on_initialize(8);
+            assert_eq!(Substratekitties::kitty(ids[0]).birth_block, 7);
+        });
+    }
+
+    #[test]
+    fn owner_can_split_shares_but_keeps_a_majority() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_eq!(Substratekitties::shares_of(kitty_id, &ALICE), TOTAL_SHARES);
+
+            assert_ok!(Substratekitties::transfer_shares(Origin::signed(ALICE), kitty_id, BOB, 3_000));
+
+            assert_eq!(Substratekitties::shares_of(kitty_id, &ALICE), 7_000);
+            assert_eq!(Substratekitties::shares_of(kitty_id, &BOB), 3_000);
+            assert_eq!(Substratekitties::shareholders(kitty_id), vec![ALICE, BOB]);
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+
+            assert_noop!(
+                Substratekitties::transfer_shares(Origin::signed(ALICE), kitty_id, CHARLIE, 2_000),
+                Error::OwnerNeedsMajority.as_str()
+            );
+            assert_noop!(
+                Substratekitties::transfer_shares(Origin::signed(BOB), kitty_id, CHARLIE, 3_001),
+                Error::NotEnoughShares.as_str()
+            );
+            assert_noop!(
+                Substratekitties::burn_kitty(Origin::signed(ALICE), kitty_id),
+                Error::CoOwned.as_str()
+            );
+
+            // The owner's shares follow the kitty, and everyone else keeps theirs
+            assert_ok!(Substratekitties::transfer(Origin::signed(ALICE), CHARLIE, kitty_id));
+            assert_eq!(Substratekitties::shares_of(kitty_id, &ALICE), 0);
+            assert_eq!(Substratekitties::shares_of(kitty_id, &CHARLIE), 7_000);
+            assert_eq!(Substratekitties::shares_of(kitty_id, &BOB), 3_000);
+
+            // Handing every share back to the owner makes the kitty wholly theirs again
+            assert_ok!(Substratekitties::transfer_shares(Origin::signed(BOB), kitty_id, CHARLIE, 3_000));
+            assert_eq!(Substratekitties::shares_of(kitty_id, &CHARLIE), TOTAL_SHARES);
+            assert!(!<Shareholders<Test>>::exists(kitty_id));
+            assert!(!<KittyShares<Test>>::exists((kitty_id, CHARLIE)));
+        });
+    }
+
+    #[test]
+    fn sale_pays_shareholders_pro_rata() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::transfer_shares(Origin::signed(ALICE), kitty_id, BOB, 3_000));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(CHARLIE), kitty_id, 100));
+
+            assert_eq!(Balances::free_balance(&ALICE), 1_070);
+            assert_eq!(Balances::free_balance(&BOB), 1_030);
+            assert_eq!(Balances::free_balance(&CHARLIE), 900);
+            assert_eq!(Substratekitties::shares_of(kitty_id, &CHARLIE), TOTAL_SHARES);
+            assert_eq!(Substratekitties::shares_of(kitty_id, &BOB), 0);
+            assert!(!<Shareholders<Test>>::exists(kitty_id));
+        });
+    }
+
+    #[test]
+    fn owned_sorted_by_price_puts_the_dearest_first() {
+        with_externalities(&mut new_test_ext(), || {
+            let ids = create_kitties_for(ALICE, 3);
+            create_kitty_for(BOB);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[0], 20, 0));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[1], 300, 0));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[2], 100, 0));
+
+            assert_eq!(
+                Substratekitties::owned_sorted_by_price(&ALICE),
+                vec![(ids[1], 300), (ids[2], 100), (ids[0], 20)]
+            );
+            assert_eq!(Substratekitties::owned_sorted_by_price(&CHARLIE), vec![]);
+        });
+    }
+
+    #[test]
+    fn breed_with_sire_pays_nothing_unless_every_fee_is_affordable() {
+        with_externalities(&mut new_test_ext(), || {
+            let mine = create_kitty_for(ALICE);
+            let sire = create_kitty_for(BOB);
+            assert_ok!(Substratekitties::set_treasury(Origin::ROOT, CHARLIE));
+            // Alice can cover the sire fee, but not the breed fee on top of it
+            assert_ok!(Substratekitties::set_sire_price(Origin::signed(BOB), sire, 998));
+
+            assert_noop!(
+                Substratekitties::breed_with_sire(Origin::signed(ALICE), mine, sire, 998),
+                Error::CannotAffordFees.as_str()
+            );
+
+            assert_eq!(Balances::free_balance(&ALICE), 1_000);
+            assert_eq!(Balances::free_balance(&BOB), 1_000);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_000);
+            assert_eq!(Substratekitties::owned_kitty_count(&ALICE), 1);
+        });
+    }
+
+    #[test]
+    fn sale_pays_nothing_if_a_shareholder_cannot_be_paid() {
+        with_externalities(&mut new_test_ext(), || {
+            assert_ok!(Substratekitties::set_fee_collector(Origin::ROOT, ALICE));
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::transfer_shares(Origin::signed(ALICE), kitty_id, BOB, 3_000));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+            // Bob's share of the sale would overflow his balance
+            TestBalances::make_free_balance_be(&BOB, u64::max_value());
+            PAYMENTS.with(|payments| payments.borrow_mut().clear());
+
+            assert_noop!(
+                Substratekitties::buy_kitty(Origin::signed(CHARLIE), kitty_id, 100),
+                Error::Overflow.as_str()
+            );
+
+            assert_eq!(payments(), vec![]);
+            assert_eq!(Balances::free_balance(&ALICE), 1_000);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_000);
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+            assert_eq!(Substratekitties::shares_of(kitty_id, &BOB), 3_000);
+        });
+    }
+
+    #[test]
+    fn failed_settlement_pays_nobody_if_a_later_payment_fails() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            assert_ok!(Substratekitties::set_fee_collector(Origin::ROOT, CHARLIE));
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::transfer_shares(Origin::signed(ALICE), kitty_id, CHARLIE, 3_000));
+            assert_ok!(Substratekitties::create_auction(Origin::signed(ALICE), kitty_id, 50, 10, 0));
+            assert_ok!(Substratekitties::bid(Origin::signed(BOB), kitty_id, 100));
+
+            // The marketplace fee could be paid, but Alice's own payout would overflow her balance
+            TestBalances::make_free_balance_be(&ALICE, u64::max_value());
+            PAYMENTS.with(|payments| payments.borrow_mut().clear());
+            System::set_block_number(11);
+            assert_ok!(Substratekitties::settle_auction(Origin::signed(CHARLIE), kitty_id));
+
+            assert_eq!(kitty_events().last(), Some(&TestEvent::kitties(RawEvent::AuctionSettleFailed(kitty_id))));
+            assert_eq!(payments(), vec![]);
+            assert_eq!(Balances::reserved_balance(&BOB), 0);
+            assert_eq!(Balances::free_balance(&BOB), 1_000);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_000);
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+            assert_eq!(Substratekitties::shares_of(kitty_id, &CHARLIE), 3_000);
+        });
+    }
+
+    #[test]
+    fn offers_can_be_raised_and_lowered_up_to_the_whole_balance() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_ok!(Substratekitties::make_offer(Origin::signed(BOB), kitty_id, 600));
+            assert_ok!(Substratekitties::make_offer(Origin::signed(BOB), kitty_id, 1_000));
+            assert_eq!(Balances::reserved_balance(&BOB), 1_000);
+            assert_eq!(Balances::free_balance(&BOB), 0);
+
+            assert!(Substratekitties::make_offer(Origin::signed(BOB), kitty_id, 1_001).is_err());
+            assert_eq!(Substratekitties::offer((kitty_id, BOB)), 1_000);
+
+            assert_ok!(Substratekitties::make_offer(Origin::signed(BOB), kitty_id, 200));
+            assert_eq!(Balances::reserved_balance(&BOB), 200);
+            assert_eq!(Balances::free_balance(&BOB), 800);
+            assert_eq!(Substratekitties::offer((kitty_id, BOB)), 200);
+        });
+    }
+
+    #[test]
+    fn the_high_bidder_can_raise_their_bid_up_to_their_whole_balance() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::create_auction(Origin::signed(ALICE), kitty_id, 50, 10, 0));
+
+            assert_ok!(Substratekitties::bid(Origin::signed(BOB), kitty_id, 600));
+            assert_ok!(Substratekitties::bid(Origin::signed(BOB), kitty_id, 1_000));
+
+            assert_eq!(Balances::reserved_balance(&BOB), 1_000);
+            assert_eq!(Balances::free_balance(&BOB), 0);
+            assert_eq!(Substratekitties::auction(kitty_id).map(|auction| auction.high_bid), Some(1_000));
+        });
+    }
+
+    #[test]
+    fn create_auction_rejects_a_duration_past_the_last_block() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::create_auction(Origin::signed(ALICE), kitty_id, 50, u64::max_value(), 0),
+                Error::Overflow.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn a_kitty_on_sale_cannot_be_leased() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let ids = create_kitties_for(ALICE, 3);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[0], 100, 0));
+            assert_ok!(Substratekitties::create_auction(Origin::signed(ALICE), ids[1], 50, 10, 0));
+
+            assert_noop!(
+                Substratekitties::lease_kitty(Origin::signed(ALICE), ids[0], BOB, 10, 0),
+                Error::ListedForSale.as_str()
+            );
+            assert_noop!(
+                Substratekitties::lease_kitty(Origin::signed(ALICE), ids[1], BOB, 10, 0),
+                Error::InAuction.as_str()
+            );
+
+            // Listing after offering the lease stops it being accepted
+            assert_ok!(Substratekitties::lease_kitty(Origin::signed(ALICE), ids[2], BOB, 10, 0));
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), ids[2], 100, 0));
+            assert_noop!(
+                Substratekitties::accept_lease(Origin::signed(BOB), ids[2]),
+                Error::ListedForSale.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn starting_a_lease_refunds_open_offers() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::make_offer(Origin::signed(CHARLIE), kitty_id, 100));
+            assert_ok!(Substratekitties::lease_kitty(Origin::signed(ALICE), kitty_id, BOB, 10, 0));
+
+            assert_ok!(Substratekitties::accept_lease(Origin::signed(BOB), kitty_id));
+
+            assert_eq!(Substratekitties::offer((kitty_id, CHARLIE)), 0);
+            assert_eq!(Substratekitties::best_offer(kitty_id), None);
+            assert_eq!(Balances::reserved_balance(&CHARLIE), 0);
+            assert_eq!(Balances::free_balance(&CHARLIE), 1_000);
+        });
+    }
+
+    #[test]
+    fn accept_lease_rejects_a_lease_past_the_last_block() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::lease_kitty(Origin::signed(ALICE), kitty_id, BOB, u64::max_value(), 50));
+
+            assert_noop!(
+                Substratekitties::accept_lease(Origin::signed(BOB), kitty_id),
+                Error::Overflow.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn set_price_rejects_an_expiry_past_the_last_block() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+
+            assert_noop!(
+                Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, u64::max_value()),
+                Error::Overflow.as_str()
+            );
+        });
+    }
+
+    #[test]
+    fn force_transfer_clears_whatever_holds_the_kitty() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::create_auction(Origin::signed(ALICE), kitty_id, 50, 10, 0));
+            assert_ok!(Substratekitties::bid(Origin::signed(BOB), kitty_id, 100));
+            // `lock_kitty` refuses auctioned kitties, but root has to cope with both at once
+            <LockedKitties<Test>>::insert(kitty_id, true);
+            let bob_kitty = create_kitty_for(BOB);
+            assert_ok!(Substratekitties::propose_swap(Origin::signed(ALICE), kitty_id, bob_kitty, BOB));
+            let gift_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::offer_gift(Origin::signed(ALICE), gift_id, BOB));
+
+            assert_ok!(Substratekitties::force_transfer(Origin::ROOT, kitty_id, CHARLIE));
+            assert_ok!(Substratekitties::force_transfer(Origin::ROOT, gift_id, CHARLIE));
+
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(CHARLIE));
+            assert!(!Substratekitties::is_locked(kitty_id));
+            assert_eq!(Substratekitties::auction(kitty_id), None);
+            assert_eq!(Balances::reserved_balance(&BOB), 0);
+            assert_eq!(Balances::free_balance(&BOB), 1_000);
+            assert_eq!(Substratekitties::swap_proposal(0), None);
+            assert_eq!(Substratekitties::swaps_of(kitty_id), vec![]);
+            assert_eq!(Substratekitties::swaps_of(bob_kitty), vec![]);
+            assert_eq!(Substratekitties::owner_of(gift_id), Some(CHARLIE));
+            assert_eq!(Substratekitties::gift_recipient(gift_id), None);
+            assert_eq!(Substratekitties::gift_giver(gift_id), None);
+            assert_noop!(
+                Substratekitties::claim_gift(Origin::signed(BOB), gift_id),
+                Error::NoGift.as_str()
+            );
+            assert_indices_consistent(&[ALICE, BOB, CHARLIE]);
+
+            // The new owner is free to move the kitty on
+            assert_ok!(Substratekitties::transfer(Origin::signed(CHARLIE), BOB, kitty_id));
+        });
+    }
+
+    #[test]
+    fn a_failed_accept_leaves_the_offer_in_place() {
+        with_externalities(&mut new_test_ext(), || {
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::make_offer(Origin::signed(BOB), kitty_id, 100));
+            // Alice's payment would overflow her balance
+            TestBalances::make_free_balance_be(&ALICE, u64::max_value());
+
+            assert_noop!(
+                Substratekitties::accept_offer(Origin::signed(ALICE), kitty_id, BOB),
+                Error::Overflow.as_str()
+            );
+
+            assert_eq!(Substratekitties::offer((kitty_id, BOB)), 100);
+            assert_eq!(Substratekitties::offerers_of(kitty_id), vec![BOB]);
+            assert_eq!(Substratekitties::best_offer(kitty_id), Some((BOB, 100)));
+            assert_eq!(Balances::reserved_balance(&BOB), 100);
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(ALICE));
+        });
+    }
+
+    #[test]
+    fn the_module_account_holds_more_gifts_than_an_account_may_own() {
+        with_externalities(&mut new_test_ext_for::<CappedTest>(), || {
+            for _ in 0..2 {
+                assert_ok!(CappedSubstratekitties::create_kitties(Origin::signed(ALICE), 2));
+                for kitty_id in CappedSubstratekitties::kitties_of(&ALICE) {
+                    assert_ok!(CappedSubstratekitties::offer_gift(Origin::signed(ALICE), kitty_id, BOB));
+                }
+            }
+
+            let module_account = CappedSubstratekitties::module_account_id();
+            assert_eq!(CappedSubstratekitties::owned_kitty_count(&module_account), 4);
+        });
+    }
+
+    #[test]
+    fn escrow_does_not_restart_the_transfer_cooldown() {
+        with_externalities(&mut new_test_ext_for::<SlowTradeTest>(), || {
+            system::Module::<SlowTradeTest>::set_block_number(1);
+            assert_ok!(SlowTradeSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            let kitty_id = SlowTradeSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+            assert_ok!(SlowTradeSubstratekitties::transfer(Origin::signed(ALICE), BOB, kitty_id));
+
+            // Bob can hand the kitty to escrow straight away, but Charlie still has to wait out
+            // the cooldown from Bob's transfer before claiming it
+            assert_ok!(SlowTradeSubstratekitties::offer_gift(Origin::signed(BOB), kitty_id, CHARLIE));
+            assert_noop!(
+                SlowTradeSubstratekitties::claim_gift(Origin::signed(CHARLIE), kitty_id),
+                Error::TransferredTooRecently.as_str()
+            );
+
+            system::Module::<SlowTradeTest>::set_block_number(6);
+            assert_ok!(SlowTradeSubstratekitties::claim_gift(Origin::signed(CHARLIE), kitty_id));
+        });
+    }
+
+    #[test]
+    fn deposits_and_gifts_share_the_module_account_without_filling_it() {
+        with_externalities(&mut new_test_ext_for::<CappedTest>(), || {
+            assert_ok!(CappedSubstratekitties::create_kitties(Origin::signed(ALICE), 2));
+            let ids = CappedSubstratekitties::kitties_of(&ALICE);
+            assert_ok!(CappedSubstratekitties::offer_gift(Origin::signed(ALICE), ids[0], BOB));
+            assert_ok!(CappedSubstratekitties::offer_gift(Origin::signed(ALICE), ids[1], BOB));
+
+            assert_ok!(CappedSubstratekitties::create_kitties(Origin::signed(ALICE), 2));
+            for kitty_id in CappedSubstratekitties::kitties_of(&ALICE) {
+                assert_ok!(CappedSubstratekitties::deposit_to_module(Origin::signed(ALICE), kitty_id));
+            }
+
+            let module_account = CappedSubstratekitties::module_account_id();
+            assert_eq!(CappedSubstratekitties::owned_kitty_count(&module_account), 4);
+        });
+    }
+
+    #[test]
+    fn the_lessee_breeds_a_leased_kitty() {
+        with_externalities(&mut new_test_ext(), || {
+            System::set_block_number(1);
+            let leased = create_kitty_for(ALICE);
+            let bobs = create_kitty_for(BOB);
+            let sire = create_kitty_for(CHARLIE);
+            assert_ok!(Substratekitties::set_sire_price(Origin::signed(CHARLIE), sire, 10));
+            assert_ok!(Substratekitties::lease_kitty(Origin::signed(ALICE), leased, BOB, 10, 0));
+            assert_ok!(Substratekitties::accept_lease(Origin::signed(BOB), leased));
+
+            // The owner has given up the use of the kitty while it is leased
+            assert_noop!(
+                Substratekitties::breed_with_sire(Origin::signed(ALICE), leased, sire, 10),
+                Error::NotOwner.as_str()
+            );
+
+            assert_ok!(Substratekitties::breed_kitty(Origin::signed(BOB), leased, bobs));
+
+            let child = Substratekitties::kitty_of_owner_by_index((BOB, 1));
+            assert_eq!(Substratekitties::parents_of(child), (leased, bobs));
+            assert_eq!(Substratekitties::owner_of(leased), Some(ALICE));
+        });
+    }
+
+    #[test]
+    fn sale_pays_nothing_if_the_buyer_cannot_cover_the_transfer_fees() {
+        with_externalities(&mut new_test_ext_with_fees(1, 20), || {
+            // A collector without an account yet, so paying it costs the creation fee
+            let collector = 7;
+            let kitty_id = create_kitty_for(ALICE);
+            assert_ok!(Substratekitties::set_price(Origin::signed(ALICE), kitty_id, 100, 0));
+            assert_ok!(Substratekitties::set_fee_collector(Origin::ROOT, collector));
+
+            // 10 to the collector costs 30 and 90 to Alice costs 91, so 120 is one short. The
+            // collector could be paid, but not Alice after it.
+            TestBalances::make_free_balance_be(&CHARLIE, 120);
+            PAYMENTS.with(|payments| payments.borrow_mut().clear());
+            assert_noop!(
+                Substratekitties::buy_kitty(Origin::signed(CHARLIE), kitty_id, 100),
+                Error::InsufficientBalance.as_str()
+            );
+            assert_eq!(payments(), vec![]);
+            assert_eq!(Balances::free_balance(&collector), 0);
+
+            TestBalances::make_free_balance_be(&CHARLIE, 121);
+            assert_ok!(Substratekitties::buy_kitty(Origin::signed(CHARLIE), kitty_id, 100));
+            assert_eq!(payments(), vec![(CHARLIE, collector, 10), (CHARLIE, ALICE, 90)]);
+            assert_eq!(Balances::free_balance(&CHARLIE), 0);
+            assert_eq!(Substratekitties::owner_of(kitty_id), Some(CHARLIE));
+        });
+    }
+
+    #[test]
+    fn offers_on_a_kitty_are_capped() {
+        with_externalities(&mut new_test_ext_for::<FewOffersTest>(), || {
+            let dave = 4;
+            balances::Module::<FewOffersTest>::make_free_balance_be(&dave, 1_000);
+            assert_ok!(FewOffersSubstratekitties::create_kitty(Origin::signed(ALICE)));
+            let kitty_id = FewOffersSubstratekitties::kitty_of_owner_by_index((ALICE, 0));
+
+            assert_ok!(FewOffersSubstratekitties::make_offer(Origin::signed(BOB), kitty_id, 50));
+            assert_ok!(FewOffersSubstratekitties::make_offer(Origin::signed(CHARLIE), kitty_id, 60));
+            assert_noop!(
+                FewOffersSubstratekitties::make_offer(Origin::signed(dave), kitty_id, 70),
+                Error::TooManyOffers.as_str()
+            );
+
+            // Changing an open offer doesn't take another place
+            assert_ok!(FewOffersSubstratekitties::make_offer(Origin::signed(BOB), kitty_id, 80));
+
+            assert_ok!(FewOffersSubstratekitties::withdraw_offer(Origin::signed(CHARLIE), kitty_id));
+            assert_ok!(FewOffersSubstratekitties::make_offer(Origin::signed(dave), kitty_id, 70));
+            assert_eq!(FewOffersSubstratekitties::offerers_of(kitty_id), vec![BOB, dave]);
+        });
+    }
+}
@@ -1,7 +1,7 @@
 use primitives::{ed25519, sr25519, Pair};
 use substratekitties_runtime::{
 	AccountId, GenesisConfig, ConsensusConfig, TimestampConfig, BalancesConfig,
-	SudoConfig, IndicesConfig,
+	SudoConfig, IndicesConfig, SubstratekittiesConfig,
 };
 use substrate_service;
 
@@ -115,5 +115,8 @@ fn testnet_genesis(initial_authorities: Vec<AuthorityId>, endowed_accounts: Vec<
 		sudo: Some(SudoConfig {
 			key: root_key,
 		}),
+		substratekitties: Some(SubstratekittiesConfig {
+			kitties: vec![],
+		}),
 	}
 }